@@ -0,0 +1,63 @@
+use std::{fmt, io};
+
+use strum_macros::Display;
+
+pub type ActionResult = Result<ActionOutcome, ActionError>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ActionOutcome {
+    Completed,
+    Skipped(String), // Action didn't apply to its input. The pipeline proceeds to the next action
+    Halted(String),  // Action completed, and the remaining actions shouldn't be executed
+}
+
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
+pub enum ActionErrorKind {
+    Io,           // File system operation failed
+    Spawn,        // Command process couldn't be started
+    Timeout,      // Action exceeded its time limit
+    Precondition, // Action's requirements weren't met (Missing input, existing file, etc.)
+    Command,      // Command ran but didn't succeed
+    Verification, // File's contents didn't match what was expected (Checksum mismatch)
+    Config, // Action is misconfigured. Reported when the handler is started, if checkable beforehand
+}
+
+#[derive(Clone, Debug)]
+pub struct ActionError {
+    pub kind: ActionErrorKind,
+    pub message: String,
+}
+
+impl ActionError {
+    pub fn new<T>(kind: ActionErrorKind, message: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    // Failures which may not reoccur when running the action again (Locked files, unavailable shares, flaky commands)
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.kind,
+            ActionErrorKind::Io | ActionErrorKind::Timeout | ActionErrorKind::Command
+        )
+    }
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} error - {}", self.kind, self.message)
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+impl From<io::Error> for ActionError {
+    fn from(err: io::Error) -> Self {
+        Self::new(ActionErrorKind::Io, err.to_string())
+    }
+}
@@ -0,0 +1,65 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+const EXTENSIONS: [(&str, ArchiveFormat); 5] = [
+    (".zip", ArchiveFormat::Zip),
+    (".tar.gz", ArchiveFormat::TarGz),
+    (".tgz", ArchiveFormat::TarGz),
+    (".tar.zst", ArchiveFormat::TarZst),
+    (".tzst", ArchiveFormat::TarZst),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArchiveFormat {
+    #[serde(rename = "zip")]
+    Zip,
+    #[serde(rename = "tar.gz")]
+    TarGz,
+    #[serde(rename = "tar.zst")]
+    TarZst,
+}
+
+impl ArchiveFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::TarGz => "tar.gz",
+            ArchiveFormat::TarZst => "tar.zst",
+        }
+    }
+
+    // Format by the file's extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::split_file_name(path).map(|(format, _)| format)
+    }
+
+    // File name without the archive's extension - `logs.tar.gz` is `logs`
+    pub fn stem(path: &Path) -> Option<String> {
+        Self::split_file_name(path).map(|(_, stem)| stem)
+    }
+
+    fn split_file_name(path: &Path) -> Option<(Self, String)> {
+        let file_name = path.file_name()?.to_string_lossy();
+        EXTENSIONS.iter().find_map(|(extension, format)| {
+            let stem_len = file_name.len().checked_sub(extension.len())?;
+            if stem_len == 0 || !file_name.is_char_boundary(stem_len) {
+                return None;
+            }
+            let (stem, file_extension) = file_name.split_at(stem_len);
+            if file_extension.eq_ignore_ascii_case(extension) {
+                Some((*format, stem.to_owned()))
+            } else {
+                None
+            }
+        })
+    }
+
+    // Valid compression levels. Higher compresses better but slower
+    pub fn level_range(&self) -> (i32, i32) {
+        match self {
+            ArchiveFormat::Zip | ArchiveFormat::TarGz => (0, 9),
+            ArchiveFormat::TarZst => (1, 22),
+        }
+    }
+}
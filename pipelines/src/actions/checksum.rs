@@ -0,0 +1,195 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use md5::Md5;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{
+    file_operations, ActionError, ActionErrorKind, ActionOutcome, ActionResult, PipelineAction,
+    RetryPolicy,
+};
+use crate::{
+    pipeline_context_input::PipelineContextInput,
+    pipeline_execution_context::PipelineExecutionContext,
+};
+
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumAlgorithm {
+    Sha256,
+    Blake3,
+    Md5,
+}
+
+impl ChecksumAlgorithm {
+    // Also the sidecar's extension & the placeholder's keyword
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Sha256 => "sha256",
+            ChecksumAlgorithm::Blake3 => "blake3",
+            ChecksumAlgorithm::Md5 => "md5",
+        }
+    }
+
+    // Lowercase hex digest of the file's contents
+    pub fn digest(&self, file_path: &Path) -> io::Result<String> {
+        let mut file = File::open(file_path)?;
+        match self {
+            ChecksumAlgorithm::Sha256 => digest_reader(&mut file, Sha256::new()),
+            ChecksumAlgorithm::Md5 => digest_reader(&mut file, Md5::new()),
+            ChecksumAlgorithm::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                io::copy(&mut file, &mut hasher)?;
+                Ok(hasher.finalize().to_hex().to_string())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumMode {
+    Compute, // Only sets the placeholder
+    #[default]
+    Write, // Writes the sidecar file, replacing an existing one
+    Verify,  // Fails if the file doesn't match the existing sidecar file
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Checksum {
+    pub input: PipelineContextInput,
+    pub algorithm: ChecksumAlgorithm,
+    #[serde(default)]
+    pub mode: ChecksumMode, // Defaults to write
+    pub when: Option<String>, // Condition deciding if the action is executed
+    pub retry: Option<RetryPolicy>,
+}
+
+impl Checksum {
+    // `report.pdf.sha256` next to `report.pdf`
+    fn sidecar_path(&self, file_path: &Path) -> PathBuf {
+        let mut sidecar_file_name = file_path.file_name().unwrap_or_default().to_os_string();
+        sidecar_file_name.push(".");
+        sidecar_file_name.push(self.algorithm.name());
+        file_path.with_file_name(sidecar_file_name)
+    }
+
+    fn verify(&self, file_path: &Path, sidecar_path: &Path, digest: &str) -> ActionResult {
+        let sidecar = match fs::read_to_string(sidecar_path) {
+            Ok(sidecar) => sidecar,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(ActionError::new(
+                    ActionErrorKind::Precondition,
+                    format!("Sidecar {:?} doesn't exist", sidecar_path),
+                ))
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let file_name = file_path.file_name().unwrap_or_default().to_string_lossy();
+        let expected_digest = expected_digest(&sidecar, &file_name).ok_or_else(|| {
+            ActionError::new(
+                ActionErrorKind::Precondition,
+                format!("Sidecar {:?} has no checksum of the file", sidecar_path),
+            )
+        })?;
+        if !expected_digest.eq_ignore_ascii_case(digest) {
+            return Err(ActionError::new(
+                ActionErrorKind::Verification,
+                format!(
+                    "{} mismatch - Expected {}, got {}",
+                    self.algorithm.name(),
+                    expected_digest,
+                    digest
+                ),
+            ));
+        }
+        Ok(ActionOutcome::Completed)
+    }
+}
+
+impl Default for Checksum {
+    fn default() -> Self {
+        Self {
+            input: PipelineContextInput::EventFilePath,
+            algorithm: ChecksumAlgorithm::Sha256,
+            mode: ChecksumMode::default(),
+            when: None,
+            retry: None,
+        }
+    }
+}
+
+impl PipelineAction for Checksum {
+    fn run(&self, context: &mut PipelineExecutionContext) -> ActionResult {
+        let input_path = context.get_input(self.input).ok_or_else(|| {
+            ActionError::new(ActionErrorKind::Precondition, "Input doesn't contain value")
+        })?;
+        if !input_path.is_file() {
+            return Err(ActionError::new(
+                ActionErrorKind::Precondition,
+                "Path isn't a file",
+            ));
+        }
+        let digest = self.algorithm.digest(&input_path)?;
+        context.log(format!("{} - {}", self.algorithm.name(), digest));
+        let sidecar_path = self.sidecar_path(&input_path);
+        match self.mode {
+            ChecksumMode::Compute => {}
+            ChecksumMode::Write => {
+                // Same format as `sha256sum`, so the sidecar can be verified by common tools
+                let sidecar = format!(
+                    "{}  {}\n",
+                    digest,
+                    input_path.file_name().unwrap_or_default().to_string_lossy()
+                );
+                context.mark_written(&sidecar_path);
+                file_operations::create_atomically(context, &sidecar_path, |sidecar_file| {
+                    io::Write::write_all(sidecar_file, sidecar.as_bytes())
+                })?;
+                context.log(format!("Wrote sidecar {:?}", sidecar_path));
+            }
+            ChecksumMode::Verify => {
+                self.verify(&input_path, &sidecar_path, &digest)?;
+                context.log("Verified checksum");
+            }
+        }
+        context
+            .variables
+            .insert(self.algorithm.name().to_owned(), digest);
+        context.action_file_path = Some(input_path);
+        Ok(ActionOutcome::Completed)
+    }
+}
+
+fn digest_reader<D: Digest>(reader: &mut impl Read, mut hasher: D) -> io::Result<String> {
+    let mut buffer = vec![0; READ_BUFFER_SIZE];
+    loop {
+        let read_len = reader.read(&mut buffer)?;
+        if read_len == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read_len]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+// Sidecars list `<digest>  <file name>` lines (`*` marks binary mode). A lone digest applies to any file
+fn expected_digest<'a>(sidecar: &'a str, file_name: &str) -> Option<&'a str> {
+    sidecar.lines().find_map(|line| {
+        let mut parts = line.trim().splitn(2, char::is_whitespace);
+        let digest = parts.next().filter(|digest| !digest.is_empty())?;
+        match parts
+            .next()
+            .map(|name| name.trim_start().trim_start_matches('*'))
+        {
+            Some(name) if name != file_name => None,
+            _ => Some(digest),
+        }
+    })
+}
@@ -0,0 +1,219 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    archive_format::ArchiveFormat,
+    conflict_strategy::{ConflictResolution, ConflictStrategy},
+    construct_working_dir, file_operations, ActionError, ActionErrorKind, ActionOutcome,
+    ActionResult, PipelineAction, RetryPolicy,
+};
+use crate::{
+    pipeline_context_input::PipelineContextInput,
+    pipeline_execution_context::PipelineExecutionContext,
+};
+
+const LARGE_FILE_SIZE: u64 = u32::MAX as u64; // Zip entries larger than this require ZIP64
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Compress {
+    pub input: PipelineContextInput, // File or directory to compress
+    pub format: ArchiveFormat,       // zip, tar.gz or tar.zst
+    pub level: Option<i32>, // 0-9 for zip & tar.gz, 1-22 for tar.zst. Defaults to the format's default
+    pub output_path: Option<String>, // Relative to the input's directory. Supports placeholders. Defaults to `$name$.<format>`
+    pub datetime_formatting: bool,
+    #[serde(default)]
+    pub delete_source: bool,
+    #[serde(default)]
+    pub allow_outside_handler_dir: bool, // Allows `delete_source` to delete inputs outside the handler's directory
+    pub on_conflict: Option<ConflictStrategy>, // Defaults to fail
+    pub when: Option<String>,                  // Condition deciding if the action is executed
+    pub retry: Option<RetryPolicy>,
+}
+
+impl Compress {
+    fn format_output_path(&self, context: &PipelineExecutionContext, input_path: &Path) -> PathBuf {
+        let mut output_path = match &self.output_path {
+            Some(output_path) => output_path.to_owned(),
+            None => format!("$name$.{}", self.format.extension()),
+        };
+        if self.datetime_formatting {
+            output_path = Self::format_datetime(output_path);
+        }
+        let output_path =
+            Self::format_placeholders(&output_path, Some(input_path), context, str::to_owned);
+        construct_working_dir(input_path, Path::new(&output_path))
+    }
+
+    fn write_archive(&self, input_path: &Path, archive_file: &mut File) -> io::Result<()> {
+        let entry_name = PathBuf::from(input_path.file_name().unwrap());
+        match self.format {
+            ArchiveFormat::Zip => {
+                let mut zip = zip::ZipWriter::new(archive_file);
+                let options = zip::write::FileOptions::default()
+                    .compression_method(zip::CompressionMethod::Deflated)
+                    .compression_level(self.level);
+                append_to_zip(&mut zip, options, input_path, &entry_name)?;
+                zip.finish()?;
+            }
+            ArchiveFormat::TarGz => {
+                let level = self
+                    .level
+                    .map_or_else(flate2::Compression::default, |level| {
+                        flate2::Compression::new(level as u32)
+                    });
+                let encoder = flate2::write::GzEncoder::new(archive_file, level);
+                append_to_tar(encoder, input_path, &entry_name)?.finish()?;
+            }
+            ArchiveFormat::TarZst => {
+                let encoder = zstd::Encoder::new(archive_file, self.level.unwrap_or(0))?; // 0 is zstd's default level
+                append_to_tar(encoder, input_path, &entry_name)?.finish()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Compress {
+    fn default() -> Self {
+        Self {
+            input: PipelineContextInput::EventFilePath,
+            format: ArchiveFormat::Zip,
+            level: None,
+            output_path: None,
+            datetime_formatting: false,
+            delete_source: false,
+            allow_outside_handler_dir: false,
+            on_conflict: None,
+            when: None,
+            retry: None,
+        }
+    }
+}
+
+impl PipelineAction for Compress {
+    fn validate(&self) -> Result<(), ActionError> {
+        let (min_level, max_level) = self.format.level_range();
+        match self.level {
+            Some(level) if level < min_level || level > max_level => Err(ActionError::new(
+                ActionErrorKind::Config,
+                format!(
+                    "Level {} of {} must be between {} and {}",
+                    level,
+                    self.format.extension(),
+                    min_level,
+                    max_level
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn run(&self, context: &mut PipelineExecutionContext) -> ActionResult {
+        let input_path = context.get_input(self.input).ok_or_else(|| {
+            ActionError::new(ActionErrorKind::Precondition, "Input doesn't contain value")
+        })?;
+        let input_metadata = fs::symlink_metadata(&input_path)
+            .map_err(|_| ActionError::new(ActionErrorKind::Precondition, "Input doesn't exist"))?;
+        if self.delete_source {
+            file_operations::ensure_inside_handler_dir(
+                context,
+                &input_path,
+                self.allow_outside_handler_dir,
+            )?;
+        }
+        let output_path = self.format_output_path(context, &input_path);
+        if output_path.starts_with(&input_path) {
+            return Err(ActionError::new(
+                ActionErrorKind::Config,
+                "Archive can't be created within the compressed directory",
+            ));
+        }
+        if let Some(output_dir_path) = output_path.parent() {
+            file_operations::create_dir_all(context, output_dir_path)?;
+        }
+        let output_path = match self
+            .on_conflict
+            .unwrap_or(ConflictStrategy::Fail)
+            .resolve(&input_path, output_path)?
+        {
+            ConflictResolution::Write(output_path) => output_path,
+            ConflictResolution::Skip(reason) => return Ok(ActionOutcome::Skipped(reason)),
+        };
+        context.mark_written(&output_path);
+        file_operations::create_atomically(context, &output_path, |archive_file| {
+            self.write_archive(&input_path, archive_file)
+        })?;
+        context.log(format!("Created archive {:?}", output_path));
+        if self.delete_source {
+            context.mark_written(&input_path);
+            if input_metadata.is_dir() {
+                fs::remove_dir_all(&input_path)?;
+            } else {
+                fs::remove_file(&input_path)?;
+            }
+            context.log("Deleted source");
+        }
+        context.action_file_path = Some(output_path);
+        Ok(ActionOutcome::Completed)
+    }
+}
+
+// Adds the file, or the directory with everything within it.
+// Symbolic links are added as links - Linked files & directories may be outside of the input, or link back to it
+fn append_to_zip<W>(
+    zip: &mut zip::ZipWriter<W>,
+    options: zip::write::FileOptions,
+    path: &Path,
+    entry_name: &Path,
+) -> io::Result<()>
+where
+    W: Write + io::Seek,
+{
+    // Zip entries are always separated by `/`
+    let entry_name = entry_name
+        .iter()
+        .map(|component| component.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.file_type().is_symlink() {
+        let target_path = fs::read_link(path)?;
+        zip.add_symlink(entry_name, target_path.to_string_lossy(), options)?;
+    } else if metadata.is_dir() {
+        zip.add_directory(entry_name.as_str(), options)?;
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            append_to_zip(
+                zip,
+                options,
+                &entry.path(),
+                &Path::new(&entry_name).join(entry.file_name()),
+            )?;
+        }
+    } else {
+        let mut file = File::open(path)?;
+        let large_file = metadata.len() >= LARGE_FILE_SIZE;
+        zip.start_file(entry_name.as_str(), options.large_file(large_file))?;
+        io::copy(&mut file, zip)?;
+    }
+    Ok(())
+}
+
+fn append_to_tar<W>(writer: W, path: &Path, entry_name: &Path) -> io::Result<W>
+where
+    W: Write,
+{
+    let mut tar = tar::Builder::new(writer);
+    tar.follow_symlinks(false);
+    if fs::symlink_metadata(path)?.is_dir() {
+        tar.append_dir_all(entry_name, path)?;
+    } else {
+        tar.append_path_with_name(path, entry_name)?;
+    }
+    tar.into_inner()
+}
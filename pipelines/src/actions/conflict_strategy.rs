@@ -0,0 +1,156 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{ActionError, ActionErrorKind};
+
+const COMPARE_BUFFER_SIZE: usize = 64 * 1024;
+
+// How to handle a file already existing at an action's target path
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    Fail,            // Action fails
+    Overwrite,       // Existing file is replaced
+    Skip,            // Action is skipped
+    RenameSuffix,    // Numbered suffix is added - `report (1).pdf`
+    TimestampSuffix, // Current datetime suffix is added - `report_20211018-153000.pdf`
+    KeepNewer,       // Existing file is replaced if older, otherwise skipped
+    KeepLarger,      // Existing file is replaced if smaller, otherwise skipped
+    CompareHash,     // Skipped if the files' contents are identical, otherwise as rename_suffix
+}
+
+pub enum ConflictResolution {
+    Write(PathBuf), // Path to write the file to
+    Skip(String),
+}
+
+impl ConflictStrategy {
+    // Strategy of actions configured with the legacy `replace_older_files` setting
+    pub fn from_replace_older_files(replace_older_files: bool) -> Self {
+        if replace_older_files {
+            ConflictStrategy::Overwrite
+        } else {
+            ConflictStrategy::Fail
+        }
+    }
+
+    pub fn resolve(
+        &self,
+        source_path: &Path,
+        target_path: PathBuf,
+    ) -> Result<ConflictResolution, ActionError> {
+        if !target_path.exists() {
+            return Ok(ConflictResolution::Write(target_path));
+        }
+        Ok(match self {
+            ConflictStrategy::Fail => {
+                return Err(ActionError::new(
+                    ActionErrorKind::Precondition,
+                    "Can't replace older file",
+                ))
+            }
+            ConflictStrategy::Overwrite => ConflictResolution::Write(target_path),
+            ConflictStrategy::Skip => {
+                ConflictResolution::Skip(String::from("Target file already exists"))
+            }
+            ConflictStrategy::RenameSuffix => {
+                ConflictResolution::Write(numbered_file_path(&target_path)?)
+            }
+            ConflictStrategy::TimestampSuffix => {
+                let suffix = chrono::Local::now().format("_%Y%m%d-%H%M%S").to_string();
+                let timestamped_file_path = suffixed_file_path(&target_path, &suffix);
+                if timestamped_file_path.exists() {
+                    ConflictResolution::Write(numbered_file_path(&timestamped_file_path)?)
+                } else {
+                    ConflictResolution::Write(timestamped_file_path)
+                }
+            }
+            ConflictStrategy::KeepNewer => {
+                if fs::metadata(source_path)?.modified()?
+                    > fs::metadata(&target_path)?.modified()?
+                {
+                    ConflictResolution::Write(target_path)
+                } else {
+                    ConflictResolution::Skip(String::from("Existing file is newer"))
+                }
+            }
+            ConflictStrategy::KeepLarger => {
+                if fs::metadata(source_path)?.len() > fs::metadata(&target_path)?.len() {
+                    ConflictResolution::Write(target_path)
+                } else {
+                    ConflictResolution::Skip(String::from("Existing file is larger"))
+                }
+            }
+            ConflictStrategy::CompareHash => {
+                if have_same_content(source_path, &target_path)? {
+                    ConflictResolution::Skip(String::from("Identical file already exists"))
+                } else {
+                    ConflictResolution::Write(numbered_file_path(&target_path)?)
+                }
+            }
+        })
+    }
+}
+
+// Adds the suffix to the file's stem, keeping its extension
+fn suffixed_file_path(file_path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = file_path.file_stem().unwrap_or_default().to_os_string();
+    file_name.push(suffix);
+    if let Some(extension) = file_path.extension() {
+        file_name.push(".");
+        file_name.push(extension);
+    }
+    file_path.with_file_name(file_name)
+}
+
+// First `name (n).ext` not naming an existing file
+fn numbered_file_path(file_path: &Path) -> Result<PathBuf, ActionError> {
+    (1..=u16::MAX)
+        .map(|number| suffixed_file_path(file_path, &format!(" ({})", number)))
+        .find(|numbered_file_path| !numbered_file_path.exists())
+        .ok_or_else(|| {
+            ActionError::new(
+                ActionErrorKind::Precondition,
+                "No available numbered file name",
+            )
+        })
+}
+
+// Compares the files' contents directly - Equivalent to comparing their hashes, without reading the files in full
+fn have_same_content(first_path: &Path, second_path: &Path) -> io::Result<bool> {
+    if fs::metadata(first_path)?.len() != fs::metadata(second_path)?.len() {
+        return Ok(false);
+    }
+    let mut first_file = File::open(first_path)?;
+    let mut second_file = File::open(second_path)?;
+    let mut first_buffer = vec![0; COMPARE_BUFFER_SIZE];
+    let mut second_buffer = vec![0; COMPARE_BUFFER_SIZE];
+    loop {
+        let read = read_full(&mut first_file, &mut first_buffer)?;
+        if read != read_full(&mut second_file, &mut second_buffer)? {
+            return Ok(false);
+        }
+        if read == 0 {
+            return Ok(true);
+        }
+        if first_buffer[..read] != second_buffer[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+fn read_full(file: &mut File, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut total = 0;
+    while total < buffer.len() {
+        match file.read(&mut buffer[total..])? {
+            0 => break,
+            read => total += read,
+        }
+    }
+    Ok(total)
+}
@@ -0,0 +1,404 @@
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    checksum::ChecksumAlgorithm,
+    conflict_strategy::{ConflictResolution, ConflictStrategy},
+    construct_working_dir, file_operations, ActionError, ActionErrorKind, ActionOutcome,
+    ActionResult, PipelineAction, RetryPolicy,
+};
+use crate::{
+    pipeline_context_input::PipelineContextInput,
+    pipeline_execution_context::PipelineExecutionContext,
+};
+
+const DEFAULT_DUPLICATES_DIR: &str = "duplicates";
+const MIN_COMPACTED_RECORDS: usize = 1000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateStrategy {
+    Delete,
+    #[default]
+    Move, // Moves the duplicate to `duplicates_dir`
+    Hardlink, // Replaces the duplicate with a hard link to the original file
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Deduplicate {
+    pub input: PipelineContextInput,
+    pub algorithm: Option<ChecksumAlgorithm>, // Defaults to sha256
+    #[serde(default)]
+    pub on_duplicate: DuplicateStrategy, // Defaults to move
+    pub duplicates_dir: Option<String>, // Relative to the duplicate's directory. Supports placeholders. Defaults to `duplicates`
+    pub when: Option<String>,           // Condition deciding if the action is executed
+    pub retry: Option<RetryPolicy>,
+}
+
+// First line of the index file
+#[derive(Serialize, Deserialize)]
+struct IndexHeader {
+    algorithm: ChecksumAlgorithm,
+}
+
+// Following lines, each setting or removing (No path) the file of a content hash
+#[derive(Serialize, Deserialize)]
+struct IndexRecord {
+    digest: String,
+    path: Option<PathBuf>,
+}
+
+// Content hashes of files the handler has seen, mapped to their paths.
+// Changes are appended to the index file, which is rewritten without outdated records once they're most of it
+#[derive(Debug)]
+pub struct DeduplicationIndex {
+    algorithm: ChecksumAlgorithm,
+    files: HashMap<String, PathBuf>,
+    records_count: usize, // Records in the index file
+}
+
+impl DeduplicationIndex {
+    fn load(
+        context: &mut PipelineExecutionContext,
+        index_path: &Path,
+        algorithm: ChecksumAlgorithm,
+    ) -> Result<Self, ActionError> {
+        let mut index = Self {
+            algorithm,
+            files: HashMap::new(),
+            records_count: 0,
+        };
+        match fs::File::open(index_path) {
+            Ok(index_file) => {
+                if let Err(err) = index.read_records(BufReader::new(index_file)) {
+                    context.log_error(format!(
+                        "Deduplication index {:?} is corrupted, starting a new one - {}",
+                        index_path, err
+                    ));
+                    index.files.clear();
+                }
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+        // Files removed since the handler last ran aren't kept
+        index.files.retain(|_digest, file_path| file_path.is_file());
+        index.compact(context, index_path)?;
+        Ok(index)
+    }
+
+    fn read_records<R: BufRead>(&mut self, reader: R) -> Result<(), String> {
+        let mut lines = reader.lines();
+        let header = match lines.next() {
+            Some(line) => line.map_err(|err| err.to_string())?,
+            None => return Ok(()),
+        };
+        let header: IndexHeader = serde_json::from_str(&header).map_err(|err| err.to_string())?;
+        // Hashes of another algorithm can't be compared
+        if header.algorithm != self.algorithm {
+            return Ok(());
+        }
+        for line in lines {
+            let line = line.map_err(|err| err.to_string())?;
+            // A partially appended last record is left by an interrupted write
+            let record: IndexRecord = match serde_json::from_str(&line) {
+                Ok(record) => record,
+                Err(_) => continue,
+            };
+            match record.path {
+                Some(file_path) => self.files.insert(record.digest, file_path),
+                None => self.files.remove(&record.digest),
+            };
+        }
+        Ok(())
+    }
+
+    fn set(
+        &mut self,
+        context: &mut PipelineExecutionContext,
+        index_path: &Path,
+        digest: String,
+        file_path: Option<PathBuf>,
+    ) -> Result<(), ActionError> {
+        let record = IndexRecord {
+            digest,
+            path: file_path,
+        };
+        let mut line = serde_json::to_string(&record).map_err(index_error)?;
+        line.push('\n');
+        match &record.path {
+            Some(file_path) => self.files.insert(record.digest, file_path.clone()),
+            None => self.files.remove(&record.digest),
+        };
+        if self.records_count >= MIN_COMPACTED_RECORDS.max(self.files.len() * 2) {
+            return self.compact(context, index_path);
+        }
+        context.mark_written(index_path);
+        let mut index_file = match OpenOptions::new().append(true).open(index_path) {
+            Ok(index_file) => index_file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return self.compact(context, index_path)
+            }
+            Err(err) => return Err(err.into()),
+        };
+        index_file.write_all(line.as_bytes())?;
+        self.records_count += 1;
+        Ok(())
+    }
+
+    // Rewrites the index file with a record per indexed file
+    fn compact(
+        &mut self,
+        context: &mut PipelineExecutionContext,
+        index_path: &Path,
+    ) -> Result<(), ActionError> {
+        let mut data = serde_json::to_string(&IndexHeader {
+            algorithm: self.algorithm,
+        })
+        .map_err(index_error)?;
+        data.push('\n');
+        for (digest, file_path) in &self.files {
+            let record = IndexRecord {
+                digest: digest.clone(),
+                path: Some(file_path.clone()),
+            };
+            data.push_str(&serde_json::to_string(&record).map_err(index_error)?);
+            data.push('\n');
+        }
+        if let Some(index_dir_path) = index_path.parent() {
+            if !index_dir_path.as_os_str().is_empty() {
+                fs::create_dir_all(index_dir_path)?;
+            }
+        }
+        context.mark_written(index_path);
+        file_operations::create_atomically(context, index_path, |index_file| {
+            index_file.write_all(data.as_bytes())
+        })?;
+        self.records_count = self.files.len();
+        Ok(())
+    }
+}
+
+fn index_error(err: serde_json::Error) -> ActionError {
+    ActionError::new(
+        ActionErrorKind::Precondition,
+        format!("Deduplication index can't be saved - {}", err),
+    )
+}
+
+impl Deduplicate {
+    // Each handler keeps its own index, named by its directory
+    fn index_path(context: &PipelineExecutionContext) -> PathBuf {
+        let handler_id =
+            blake3::hash(context.handler_directory_path.to_string_lossy().as_bytes()).to_hex();
+        context
+            .state_dir_path
+            .join(format!("folden_dedup_{}.jsonl", &handler_id[..16]))
+    }
+
+    // Applies `f` to the handler's index, loading it on first use. Deleting the index file resets it
+    fn with_index<T, F>(
+        context: &mut PipelineExecutionContext,
+        algorithm: ChecksumAlgorithm,
+        f: F,
+    ) -> Result<T, ActionError>
+    where
+        F: FnOnce(
+            &mut DeduplicationIndex,
+            &mut PipelineExecutionContext,
+            &Path,
+        ) -> Result<T, ActionError>,
+    {
+        let index_path = Self::index_path(context);
+        let mut index = match context.deduplication_index.take() {
+            Some(index) if index.algorithm == algorithm && index_path.exists() => index,
+            _ => DeduplicationIndex::load(context, &index_path, algorithm)?,
+        };
+        let result = f(&mut index, context, &index_path);
+        context.deduplication_index = Some(index);
+        result
+    }
+
+    // The indexed file may be moved by following actions (Filed away by `MoveToDir`) - Once the pipeline ends,
+    // the last file it digested replaces it, if of the same size. Indexed files which no longer exist are removed
+    fn update_indexed_file(
+        context: &mut PipelineExecutionContext,
+        algorithm: ChecksumAlgorithm,
+        digest: String,
+        indexed_file_path: &Path,
+        len: u64,
+    ) -> Result<(), ActionError> {
+        if indexed_file_path.exists() {
+            return Ok(());
+        }
+        let final_file_path = context.action_file_path.clone().filter(|file_path| {
+            matches!(fs::metadata(file_path), Ok(metadata) if metadata.is_file() && metadata.len() == len)
+        });
+        Self::with_index(context, algorithm, |index, context, index_path| {
+            if index.files.get(&digest).map(PathBuf::as_path) != Some(indexed_file_path) {
+                return Ok(()); // Indexed again since, by another action
+            }
+            index.set(context, index_path, digest, final_file_path)
+        })
+    }
+
+    fn handle_duplicate(
+        &self,
+        context: &mut PipelineExecutionContext,
+        input_path: &Path,
+        original_path: &Path,
+    ) -> Result<(), ActionError> {
+        match self.on_duplicate {
+            DuplicateStrategy::Delete => {
+                context.mark_written(input_path);
+                fs::remove_file(input_path)?;
+                context.log(format!("Deleted duplicate of {:?}", original_path));
+                context.action_file_path = None;
+            }
+            DuplicateStrategy::Move => {
+                let duplicates_dir = self
+                    .duplicates_dir
+                    .as_deref()
+                    .unwrap_or(DEFAULT_DUPLICATES_DIR);
+                let duplicates_dir = Self::format_placeholders(
+                    duplicates_dir,
+                    Some(input_path),
+                    context,
+                    str::to_owned,
+                );
+                let duplicates_dir_path =
+                    construct_working_dir(input_path, Path::new(&duplicates_dir));
+                file_operations::create_dir_all(context, &duplicates_dir_path)?;
+                let target_path = duplicates_dir_path.join(input_path.file_name().unwrap());
+                let target_path =
+                    match ConflictStrategy::RenameSuffix.resolve(input_path, target_path)? {
+                        ConflictResolution::Write(target_path) => target_path,
+                        ConflictResolution::Skip(reason) => {
+                            return Err(ActionError::new(ActionErrorKind::Precondition, reason))
+                        }
+                    };
+                context.mark_written(input_path);
+                context.mark_written(&target_path);
+                file_operations::move_file(context, input_path, &target_path, true)?;
+                context.log(format!(
+                    "Moved duplicate of {:?} to {:?}",
+                    original_path, target_path
+                ));
+                context.action_file_path = Some(target_path);
+            }
+            DuplicateStrategy::Hardlink => {
+                context.mark_written(input_path);
+                file_operations::replace_with_hard_link(context, original_path, input_path)?;
+                context.log(format!("Linked duplicate to {:?}", original_path));
+                context.action_file_path = Some(input_path.to_path_buf());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Deduplicate {
+    fn default() -> Self {
+        Self {
+            input: PipelineContextInput::EventFilePath,
+            algorithm: None,
+            on_duplicate: DuplicateStrategy::default(),
+            duplicates_dir: None,
+            when: None,
+            retry: None,
+        }
+    }
+}
+
+impl PipelineAction for Deduplicate {
+    fn run(&self, context: &mut PipelineExecutionContext) -> ActionResult {
+        let input_path = context.get_input(self.input).ok_or_else(|| {
+            ActionError::new(ActionErrorKind::Precondition, "Input doesn't contain value")
+        })?;
+        let metadata = fs::symlink_metadata(&input_path)?;
+        if !metadata.is_file() {
+            return Err(ActionError::new(
+                ActionErrorKind::Precondition,
+                "Path isn't a file",
+            ));
+        }
+        if metadata.len() == 0 {
+            return Ok(ActionOutcome::Skipped(String::from("File is empty")));
+        }
+        let algorithm = self.algorithm.unwrap_or(ChecksumAlgorithm::Sha256);
+        let digest = algorithm.digest(&input_path)?;
+        context
+            .variables
+            .insert(algorithm.name().to_owned(), digest.clone());
+        // An indexed file which changed since doesn't count
+        let original_path =
+            Self::with_index(context, algorithm, |index, _context, _index_path| {
+                Ok(index.files.get(&digest).cloned())
+            })?
+            .filter(|original_path| {
+                *original_path != input_path
+                    && original_path.is_file()
+                    && algorithm.digest(original_path).ok().as_ref() == Some(&digest)
+            });
+        match original_path {
+            Some(original_path) if is_same_file(&original_path, &input_path)? => Ok(
+                ActionOutcome::Skipped(format!("Already linked to {:?}", original_path)),
+            ),
+            Some(original_path) => {
+                self.handle_duplicate(context, &input_path, &original_path)?;
+                Ok(ActionOutcome::Halted(format!(
+                    "Duplicate of {:?}",
+                    original_path
+                )))
+            }
+            None => {
+                Self::with_index(context, algorithm, |index, context, index_path| {
+                    index.set(
+                        context,
+                        index_path,
+                        digest.clone(),
+                        Some(input_path.clone()),
+                    )
+                })?;
+                let indexed_file_path = input_path.clone();
+                let len = metadata.len();
+                context.on_finished.push(Box::new(move |context| {
+                    if let Err(err) = Self::update_indexed_file(
+                        context,
+                        algorithm,
+                        digest,
+                        &indexed_file_path,
+                        len,
+                    ) {
+                        context.log_error(format!("Deduplication index wasn't updated - {}", err));
+                    }
+                }));
+                context.log("Indexed file");
+                context.action_file_path = Some(input_path);
+                Ok(ActionOutcome::Completed)
+            }
+        }
+    }
+}
+
+// Hard links of the same file don't take additional space
+#[cfg(unix)]
+fn is_same_file(first_path: &Path, second_path: &Path) -> io::Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let first_metadata = fs::metadata(first_path)?;
+    let second_metadata = fs::metadata(second_path)?;
+    Ok(first_metadata.dev() == second_metadata.dev()
+        && first_metadata.ino() == second_metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn is_same_file(_first_path: &Path, _second_path: &Path) -> io::Result<bool> {
+    Ok(false)
+}
@@ -0,0 +1,83 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    file_operations, ActionError, ActionErrorKind, ActionOutcome, ActionResult, PipelineAction,
+    RetryPolicy,
+};
+use crate::{
+    pipeline_context_input::PipelineContextInput,
+    pipeline_execution_context::PipelineExecutionContext,
+};
+
+const OVERWRITE_BUFFER_SIZE: usize = 64 * 1024;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeleteFile {
+    pub input: PipelineContextInput,
+    #[serde(default)]
+    pub secure_overwrite: bool, // File's contents are overwritten with zeros before it's deleted
+    #[serde(default)]
+    pub allow_outside_handler_dir: bool,
+    pub when: Option<String>, // Condition deciding if the action is executed
+    pub retry: Option<RetryPolicy>,
+}
+
+impl Default for DeleteFile {
+    fn default() -> Self {
+        Self {
+            input: PipelineContextInput::EventFilePath,
+            secure_overwrite: false,
+            allow_outside_handler_dir: false,
+            when: None,
+            retry: None,
+        }
+    }
+}
+
+impl PipelineAction for DeleteFile {
+    fn run(&self, context: &mut PipelineExecutionContext) -> ActionResult {
+        let input_path = context.get_input(self.input).ok_or_else(|| {
+            ActionError::new(ActionErrorKind::Precondition, "Input doesn't contain value")
+        })?;
+        let metadata = fs::symlink_metadata(&input_path)?;
+        if !metadata.is_file() && !metadata.file_type().is_symlink() {
+            return Err(ActionError::new(
+                ActionErrorKind::Precondition,
+                "Path isn't a file",
+            ));
+        }
+        file_operations::ensure_inside_handler_dir(
+            context,
+            &input_path,
+            self.allow_outside_handler_dir,
+        )?;
+        if self.secure_overwrite && metadata.is_file() {
+            overwrite_with_zeros(&input_path, metadata.len())?;
+            context.log("Overwrote file");
+        }
+        context.mark_written(&input_path);
+        fs::remove_file(&input_path)?;
+        context.log("Deleted file");
+        context.action_file_path = None;
+        Ok(ActionOutcome::Completed)
+    }
+}
+
+// Not a guarantee on copy-on-write file systems & SSDs, which may write the zeros elsewhere
+fn overwrite_with_zeros(file_path: &Path, len: u64) -> io::Result<()> {
+    let mut file = OpenOptions::new().write(true).open(file_path)?;
+    let zeros = vec![0; OVERWRITE_BUFFER_SIZE];
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk_len = remaining.min(OVERWRITE_BUFFER_SIZE as u64) as usize;
+        file.write_all(&zeros[..chunk_len])?;
+        remaining -= chunk_len as u64;
+    }
+    file.sync_all()
+}
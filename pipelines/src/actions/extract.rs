@@ -0,0 +1,489 @@
+use std::{
+    fs::{self, File},
+    io::{self, ErrorKind, Read},
+    path::{Component, Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    archive_format::ArchiveFormat, construct_working_dir, file_operations, ActionError,
+    ActionErrorKind, ActionOutcome, ActionResult, PipelineAction, RetryPolicy,
+};
+use crate::{
+    pipeline_context_input::PipelineContextInput,
+    pipeline_execution_context::PipelineExecutionContext,
+};
+
+const DEFAULT_MAX_ENTRIES: u64 = 10_000;
+const DEFAULT_MAX_EXTRACTED_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+const UNIX_FILE_TYPE_MASK: u32 = 0o170000;
+const UNIX_SYMLINK_TYPE: u32 = 0o120000;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Extract {
+    pub input: PipelineContextInput,
+    pub format: Option<ArchiveFormat>, // Detected by the archive's extension if not set
+    pub output_dir: Option<String>, // Relative to the archive's directory. Supports placeholders. Defaults to the archive's stem
+    pub datetime_formatting: bool,
+    #[serde(default)]
+    pub overwrite: bool, // Existing files are replaced, otherwise the extraction fails
+    #[serde(default)]
+    pub delete_source: bool,
+    #[serde(default)]
+    pub allow_outside_handler_dir: bool, // Allows `delete_source` to delete archives outside the handler's directory
+    pub max_entries: Option<u64>,         // Defaults to 10,000
+    pub max_extracted_bytes: Option<u64>, // Defaults to 4GB
+    pub when: Option<String>,             // Condition deciding if the action is executed
+    pub retry: Option<RetryPolicy>,
+}
+
+impl Extract {
+    fn format_output_dir(
+        &self,
+        context: &PipelineExecutionContext,
+        input_path: &Path,
+    ) -> Result<PathBuf, ActionError> {
+        let mut output_dir = match &self.output_dir {
+            Some(output_dir) => output_dir.to_owned(),
+            None => ArchiveFormat::stem(input_path)
+                .or_else(|| {
+                    input_path
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned())
+                })
+                .ok_or_else(|| {
+                    ActionError::new(ActionErrorKind::Precondition, "Archive has no file name")
+                })?
+                .replace('$', "$$"),
+        };
+        if self.datetime_formatting {
+            output_dir = Self::format_datetime(output_dir);
+        }
+        let output_dir =
+            Self::format_placeholders(&output_dir, Some(input_path), context, str::to_owned);
+        Ok(construct_working_dir(input_path, Path::new(&output_dir)))
+    }
+}
+
+impl Default for Extract {
+    fn default() -> Self {
+        Self {
+            input: PipelineContextInput::EventFilePath,
+            format: None,
+            output_dir: None,
+            datetime_formatting: false,
+            overwrite: false,
+            delete_source: false,
+            allow_outside_handler_dir: false,
+            max_entries: None,
+            max_extracted_bytes: None,
+            when: None,
+            retry: None,
+        }
+    }
+}
+
+impl PipelineAction for Extract {
+    fn run(&self, context: &mut PipelineExecutionContext) -> ActionResult {
+        let input_path = context.get_input(self.input).ok_or_else(|| {
+            ActionError::new(ActionErrorKind::Precondition, "Input doesn't contain value")
+        })?;
+        if !input_path.is_file() {
+            return Err(ActionError::new(
+                ActionErrorKind::Precondition,
+                "Archive doesn't exist",
+            ));
+        }
+        let format = self
+            .format
+            .or_else(|| ArchiveFormat::from_path(&input_path))
+            .ok_or_else(|| {
+                ActionError::new(
+                    ActionErrorKind::Precondition,
+                    "Archive format can't be detected by its extension",
+                )
+            })?;
+        if self.delete_source {
+            file_operations::ensure_inside_handler_dir(
+                context,
+                &input_path,
+                self.allow_outside_handler_dir,
+            )?;
+        }
+        let output_dir_path = self.format_output_dir(context, &input_path)?;
+        let creates_output_dir = !output_dir_path.exists();
+        file_operations::create_dir_all(context, &output_dir_path)?;
+        let mut extraction = Extraction {
+            context,
+            output_dir_path: &output_dir_path,
+            overwrite: self.overwrite,
+            remaining_entries: self.max_entries.unwrap_or(DEFAULT_MAX_ENTRIES),
+            remaining_bytes: self
+                .max_extracted_bytes
+                .unwrap_or(DEFAULT_MAX_EXTRACTED_BYTES),
+            extracted_files_count: 0,
+            created_paths: Vec::new(),
+        };
+        let archive_file = File::open(&input_path)?;
+        let result = match format {
+            ArchiveFormat::Zip => extraction.extract_zip(archive_file),
+            ArchiveFormat::TarGz => {
+                extraction.extract_tar(flate2::read::GzDecoder::new(archive_file))
+            }
+            ArchiveFormat::TarZst => extraction.extract_tar(zstd::Decoder::new(archive_file)?),
+        };
+        if let Err(err) = result {
+            // Partial extractions aren't left behind. Only paths created by the extraction are removed -
+            // Existing files replaced with `overwrite` are kept
+            if creates_output_dir {
+                let _ = fs::remove_dir_all(&output_dir_path);
+            } else {
+                for created_path in extraction.created_paths.iter().rev() {
+                    let _ = match fs::symlink_metadata(created_path) {
+                        Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(created_path),
+                        _ => fs::remove_file(created_path),
+                    };
+                }
+            }
+            return Err(err);
+        }
+        let extracted_files_count = extraction.extracted_files_count;
+        context.log(format!(
+            "Extracted {} files to {:?}",
+            extracted_files_count, output_dir_path
+        ));
+        if self.delete_source {
+            context.mark_written(&input_path);
+            fs::remove_file(&input_path)?;
+            context.log("Deleted source");
+        }
+        context.action_file_path = Some(output_dir_path);
+        Ok(ActionOutcome::Completed)
+    }
+}
+
+// State of a single archive's extraction, limiting the entries & bytes written
+struct Extraction<'a> {
+    context: &'a mut PipelineExecutionContext,
+    output_dir_path: &'a Path,
+    overwrite: bool,
+    remaining_entries: u64,
+    remaining_bytes: u64,
+    extracted_files_count: usize,
+    created_paths: Vec<PathBuf>, // Files & top most directories which didn't exist before the extraction
+}
+
+impl<'a> Extraction<'a> {
+    fn extract_zip(&mut self, archive_file: File) -> Result<(), ActionError> {
+        let mut archive = zip::ZipArchive::new(archive_file).map_err(zip_error)?;
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index).map_err(zip_error)?;
+            let entry_path = PathBuf::from(entry.name());
+            let is_symlink = matches!(entry.unix_mode(), Some(mode) if mode & UNIX_FILE_TYPE_MASK == UNIX_SYMLINK_TYPE);
+            if entry.is_dir() {
+                self.extract_dir(&entry_path)?;
+            } else if is_symlink {
+                self.skip_entry(&entry_path)?;
+            } else {
+                self.extract_file(&entry_path, &mut entry)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn extract_tar<R: Read>(&mut self, reader: R) -> Result<(), ActionError> {
+        let mut archive = tar::Archive::new(reader);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let entry_path = entry.path()?.into_owned();
+            match entry.header().entry_type() {
+                tar::EntryType::Directory => self.extract_dir(&entry_path)?,
+                tar::EntryType::Regular | tar::EntryType::Continuous => {
+                    self.extract_file(&entry_path, &mut entry)?
+                }
+                _ => self.skip_entry(&entry_path)?, // Links & special files aren't extracted
+            }
+        }
+        Ok(())
+    }
+
+    fn extract_dir(&mut self, entry_path: &Path) -> Result<(), ActionError> {
+        self.count_entry()?;
+        self.create_dir_all(&safe_entry_path(entry_path)?)
+    }
+
+    // Links within the output directory aren't followed, as they may point outside of it
+    fn create_dir_all(&mut self, relative_dir_path: &Path) -> Result<(), ActionError> {
+        let mut dir_path = self.output_dir_path.to_path_buf();
+        for component in relative_dir_path.components() {
+            dir_path.push(component);
+            match fs::symlink_metadata(&dir_path) {
+                Ok(metadata) if metadata.file_type().is_symlink() => {
+                    return Err(ActionError::new(
+                        ActionErrorKind::Precondition,
+                        format!("Path {:?} within the output directory is a link", dir_path),
+                    ))
+                }
+                Ok(_) => {}
+                Err(err) if err.kind() == ErrorKind::NotFound => {
+                    self.created_paths.push(dir_path);
+                    break;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        file_operations::create_dir_all(
+            self.context,
+            &self.output_dir_path.join(relative_dir_path),
+        )?;
+        Ok(())
+    }
+
+    fn extract_file<R: Read>(
+        &mut self,
+        entry_path: &Path,
+        entry: &mut R,
+    ) -> Result<(), ActionError> {
+        self.count_entry()?;
+        let relative_file_path = safe_entry_path(entry_path)?;
+        if relative_file_path.as_os_str().is_empty() {
+            return Err(ActionError::new(
+                ActionErrorKind::Precondition,
+                format!("Archive entry {:?} has no file name", entry_path),
+            ));
+        }
+        if let Some(relative_dir_path) = relative_file_path.parent() {
+            self.create_dir_all(relative_dir_path)?;
+        }
+        let file_path = self.output_dir_path.join(relative_file_path);
+        // An existing link is replaced by the renamed file, rather than written through
+        let file_exists = fs::symlink_metadata(&file_path).is_ok();
+        if file_exists && !self.overwrite {
+            return Err(ActionError::new(
+                ActionErrorKind::Precondition,
+                format!("File {:?} already exists", file_path),
+            ));
+        }
+        self.context.mark_written(&file_path);
+        // Reading one byte past the limit tells an exceeding entry apart from one exactly at it.
+        // Exceeding entries fail the write, so they never replace an existing file
+        let mut written_bytes = 0;
+        let remaining_bytes = self.remaining_bytes;
+        let result = file_operations::create_atomically(self.context, &file_path, |file| {
+            written_bytes = io::copy(&mut entry.take(remaining_bytes.saturating_add(1)), file)?;
+            if written_bytes > remaining_bytes {
+                return Err(io::Error::other("Entry exceeds the limit"));
+            }
+            Ok(())
+        });
+        if written_bytes > remaining_bytes {
+            return Err(ActionError::new(
+                ActionErrorKind::Precondition,
+                "Archive exceeds the maximum extracted bytes",
+            ));
+        }
+        result?;
+        self.extracted_files_count += 1;
+        if !file_exists {
+            self.created_paths.push(file_path);
+        }
+        self.remaining_bytes -= written_bytes;
+        Ok(())
+    }
+
+    fn skip_entry(&mut self, entry_path: &Path) -> Result<(), ActionError> {
+        self.count_entry()?;
+        self.context.log(format!(
+            "Skipped archive entry {:?} - Not a file",
+            entry_path
+        ));
+        Ok(())
+    }
+
+    fn count_entry(&mut self) -> Result<(), ActionError> {
+        if self.remaining_entries == 0 {
+            return Err(ActionError::new(
+                ActionErrorKind::Precondition,
+                "Archive exceeds the maximum entries",
+            ));
+        }
+        self.remaining_entries -= 1;
+        Ok(())
+    }
+}
+
+// Entries are extracted within the output directory - Absolute paths & `..` components are refused (Zip slip)
+fn safe_entry_path(entry_path: &Path) -> Result<PathBuf, ActionError> {
+    let mut safe_path = PathBuf::new();
+    for component in entry_path.components() {
+        match component {
+            Component::Normal(component) => safe_path.push(component),
+            Component::CurDir => {}
+            _ => {
+                return Err(ActionError::new(
+                    ActionErrorKind::Precondition,
+                    format!(
+                        "Archive entry {:?} points outside the output directory",
+                        entry_path
+                    ),
+                ))
+            }
+        }
+    }
+    Ok(safe_path)
+}
+
+fn zip_error(err: zip::result::ZipError) -> ActionError {
+    match err {
+        zip::result::ZipError::Io(err) => err.into(),
+        err => ActionError::new(
+            ActionErrorKind::Precondition,
+            format!("Invalid archive - {}", err),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, sync::Arc};
+
+    use super::*;
+    use crate::{event::HandledEvent, pipeline_config::PipelineConfig};
+
+    // Directory of a single test, removed once dropped
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "folden_extract_{}_{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn write_zip(archive_path: &Path, entries: &[(&str, &[u8])]) {
+        let mut zip = zip::ZipWriter::new(File::create(archive_path).unwrap());
+        for (name, data) in entries {
+            zip.start_file(*name, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    // Extracts the archive into `out` next to it
+    fn extract(archive_path: &Path, action: Extract) -> ActionResult {
+        let dir_path = archive_path.parent().unwrap();
+        let event = HandledEvent::new(Some(String::from("create")), archive_path.to_path_buf());
+        let (trace_tx, _trace_rx) = tokio::sync::broadcast::channel(16);
+        let mut context = PipelineExecutionContext::new(
+            &event,
+            dir_path,
+            dir_path,
+            PipelineConfig::default_new(None, None),
+            Arc::new(trace_tx),
+        );
+        Extract {
+            output_dir: Some(String::from("out")),
+            ..action
+        }
+        .run(&mut context)
+    }
+
+    fn precondition_error(result: ActionResult) -> String {
+        let err = result.unwrap_err();
+        assert_eq!(err.kind, ActionErrorKind::Precondition);
+        err.message
+    }
+
+    #[test]
+    fn zip_slip() {
+        for entry_path in ["../evil.txt", "/etc/evil.txt", "a/../../evil.txt"] {
+            assert!(safe_entry_path(Path::new(entry_path)).is_err());
+        }
+        assert_eq!(
+            safe_entry_path(Path::new("./a/b.txt")).unwrap(),
+            PathBuf::from("a/b.txt")
+        );
+
+        let test_dir = TestDir::new("zip_slip");
+        let archive_path = test_dir.0.join("slip.zip");
+        write_zip(
+            &archive_path,
+            &[("fine.txt", b"fine"), ("../evil.txt", b"evil")],
+        );
+        let message = precondition_error(extract(&archive_path, Extract::default()));
+        assert!(message.contains("points outside the output directory"));
+        assert!(!test_dir.0.join("evil.txt").exists());
+        assert!(!test_dir.0.join("out").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_within_output_dir() {
+        let test_dir = TestDir::new("links");
+        let outside_dir_path = test_dir.0.join("outside");
+        fs::create_dir_all(&outside_dir_path).unwrap();
+        fs::create_dir_all(test_dir.0.join("out")).unwrap();
+        std::os::unix::fs::symlink(&outside_dir_path, test_dir.0.join("out").join("sub")).unwrap();
+        let archive_path = test_dir.0.join("links.zip");
+        write_zip(&archive_path, &[("sub/evil.txt", b"evil")]);
+        let message = precondition_error(extract(
+            &archive_path,
+            Extract {
+                overwrite: true,
+                ..Extract::default()
+            },
+        ));
+        assert!(message.contains("is a link"));
+        assert_eq!(fs::read_dir(&outside_dir_path).unwrap().count(), 0);
+        assert!(fs::symlink_metadata(test_dir.0.join("out").join("sub"))
+            .unwrap()
+            .file_type()
+            .is_symlink());
+    }
+
+    #[test]
+    fn exceeding_bytes_keep_existing_files() {
+        let test_dir = TestDir::new("bytes");
+        let output_dir_path = test_dir.0.join("out");
+        fs::create_dir_all(&output_dir_path).unwrap();
+        fs::write(output_dir_path.join("keep.txt"), "original").unwrap();
+        let archive_path = test_dir.0.join("bomb.zip");
+        write_zip(
+            &archive_path,
+            &[("new.txt", b"new"), ("keep.txt", &[b'a'; 100])],
+        );
+        let message = precondition_error(extract(
+            &archive_path,
+            Extract {
+                overwrite: true,
+                max_extracted_bytes: Some(10),
+                ..Extract::default()
+            },
+        ));
+        assert!(message.contains("maximum extracted bytes"));
+        assert_eq!(
+            fs::read_to_string(output_dir_path.join("keep.txt")).unwrap(),
+            "original"
+        );
+        // Created files & temporary files are removed
+        let file_names: Vec<_> = fs::read_dir(&output_dir_path)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(file_names, ["keep.txt"]);
+    }
+}
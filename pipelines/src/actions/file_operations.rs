@@ -0,0 +1,173 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+use filetime::FileTime;
+
+use super::{ActionError, ActionErrorKind};
+use crate::pipeline_execution_context::PipelineExecutionContext;
+
+// Copies the file to a temporary file next to the target, which is renamed to the target once fully written.
+// So the target never contains a partially copied file
+pub fn copy_atomically(
+    context: &mut PipelineExecutionContext,
+    source_path: &Path,
+    target_path: &Path,
+    preserve_metadata: bool,
+) -> io::Result<()> {
+    let temp_file_path = temp_file_path(target_path);
+    context.mark_written(&temp_file_path);
+    let result = (|| {
+        fs::copy(source_path, &temp_file_path)?; // Also copies permissions
+        if preserve_metadata {
+            copy_metadata(source_path, &temp_file_path)?;
+        }
+        File::open(&temp_file_path)?.sync_all()?;
+        fs::rename(&temp_file_path, target_path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_file_path);
+    }
+    result
+}
+
+// Creates the target by writing to a temporary file next to it, which is renamed to the target once fully written
+pub fn create_atomically<F>(
+    context: &mut PipelineExecutionContext,
+    target_path: &Path,
+    write: F,
+) -> io::Result<()>
+where
+    F: FnOnce(&mut File) -> io::Result<()>,
+{
+    let temp_file_path = temp_file_path(target_path);
+    context.mark_written(&temp_file_path);
+    let result = (|| {
+        let mut temp_file = File::create(&temp_file_path)?;
+        write(&mut temp_file)?;
+        temp_file.sync_all()?;
+        fs::rename(&temp_file_path, target_path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_file_path);
+    }
+    result
+}
+
+// Creates the directory & its missing ancestors, marking them as written by the pipeline
+pub fn create_dir_all(context: &mut PipelineExecutionContext, dir_path: &Path) -> io::Result<()> {
+    let created_dir_paths: Vec<PathBuf> = dir_path
+        .ancestors()
+        .take_while(|path| !path.exists())
+        .map(Path::to_path_buf)
+        .collect();
+    fs::create_dir_all(dir_path)?;
+    for created_dir_path in created_dir_paths {
+        context.mark_written(created_dir_path);
+    }
+    Ok(())
+}
+
+// Moves the file by renaming it when on the same device, otherwise by copying it & deleting the source
+pub fn move_file(
+    context: &mut PipelineExecutionContext,
+    source_path: &Path,
+    target_path: &Path,
+    preserve_metadata: bool,
+) -> io::Result<()> {
+    match fs::rename(source_path, target_path) {
+        Ok(()) => Ok(()), // File itself is moved - Metadata is kept regardless
+        Err(err) if is_cross_device_error(&err) => {
+            copy_atomically(context, source_path, target_path, preserve_metadata)?;
+            fs::remove_file(source_path)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(unix)]
+fn is_cross_device_error(err: &io::Error) -> bool {
+    err.raw_os_error() == Some(libc::EXDEV)
+}
+
+#[cfg(windows)]
+fn is_cross_device_error(err: &io::Error) -> bool {
+    const ERROR_NOT_SAME_DEVICE: i32 = 17;
+    err.raw_os_error() == Some(ERROR_NOT_SAME_DEVICE)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_cross_device_error(_err: &io::Error) -> bool {
+    true
+}
+
+// Replaces the target with a hard link to the source, by renaming a link created next to it over the target
+pub fn replace_with_hard_link(
+    context: &mut PipelineExecutionContext,
+    source_path: &Path,
+    target_path: &Path,
+) -> io::Result<()> {
+    let temp_file_path = temp_file_path(target_path);
+    context.mark_written(&temp_file_path);
+    fs::hard_link(source_path, &temp_file_path)?;
+    let result = fs::rename(&temp_file_path, target_path);
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_file_path);
+    }
+    result
+}
+
+// Copies access & modification times, and on Unix extended attributes
+pub fn copy_metadata(source_path: &Path, target_path: &Path) -> io::Result<()> {
+    let metadata = fs::metadata(source_path)?;
+    filetime::set_file_times(
+        target_path,
+        FileTime::from_last_access_time(&metadata),
+        FileTime::from_last_modification_time(&metadata),
+    )?;
+    #[cfg(unix)]
+    if xattr::SUPPORTED_PLATFORM {
+        for name in xattr::list(source_path)? {
+            if let Some(value) = xattr::get(source_path, &name)? {
+                // Attributes the target's file system or permissions don't allow are skipped
+                let _ = xattr::set(target_path, &name, &value);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn temp_file_path(target_path: &Path) -> PathBuf {
+    let mut temp_file_name = OsString::from(".");
+    temp_file_name.push(target_path.file_name().unwrap_or_default());
+    temp_file_name.push(".folden-tmp");
+    target_path.with_file_name(temp_file_name)
+}
+
+// Files outside the handler's directory (Or the directory itself) are only acted on if explicitly allowed.
+// The file's directory is canonicalized, so `..` components & linked directories can't escape it.
+// The file itself isn't resolved - A symbolic link is acted on as the link, not its target
+pub fn ensure_inside_handler_dir(
+    context: &PipelineExecutionContext,
+    file_path: &Path,
+    allow_outside_handler_dir: bool,
+) -> Result<(), ActionError> {
+    if allow_outside_handler_dir {
+        return Ok(());
+    }
+    let handler_directory_path = context.handler_directory_path.canonicalize()?;
+    let file_path = match (file_path.parent(), file_path.file_name()) {
+        (Some(parent_dir_path), Some(file_name)) => parent_dir_path.canonicalize()?.join(file_name),
+        _ => file_path.canonicalize()?,
+    };
+    if file_path == handler_directory_path || !file_path.starts_with(&handler_directory_path) {
+        return Err(ActionError::new(
+            ActionErrorKind::Precondition,
+            "File is outside the handler's directory",
+        ));
+    }
+    Ok(())
+}
@@ -1,27 +1,54 @@
-use std::{
-    borrow::Cow,
-    path::{Path, PathBuf},
-};
+use std::path::{Path, PathBuf};
 
-use lazy_static::lazy_static;
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 use strum_macros::{EnumVariantNames, IntoStaticStr};
 
+mod action_outcome;
+mod archive_format;
+mod checksum;
+mod compress;
+mod conflict_strategy;
+mod deduplicate;
+mod delete_file;
+mod extract;
+mod file_operations;
 mod move_to_dir;
+mod move_to_trash;
+mod placeholders;
+mod rename_file;
+mod retry_policy;
 mod run_cmd;
-use self::{move_to_dir::MoveToDir, run_cmd::RunCmd};
+pub use self::action_outcome::{ActionError, ActionErrorKind, ActionOutcome, ActionResult};
+pub use self::deduplicate::DeduplicationIndex;
+pub use self::retry_policy::RetryPolicy;
+pub use self::run_cmd::CommandOutput;
+use self::{
+    checksum::Checksum, compress::Compress, deduplicate::Deduplicate, delete_file::DeleteFile,
+    extract::Extract, move_to_dir::MoveToDir, move_to_trash::MoveToTrash, rename_file::RenameFile,
+    run_cmd::RunCmd,
+};
 use crate::pipeline_execution_context::PipelineExecutionContext;
 
 pub trait PipelineAction {
-    // Execute action. Returns if action deemed successful.
-    fn run(&self, context: &mut PipelineExecutionContext) -> bool;
+    // Execute action. Returns the action's outcome, or why it failed.
+    fn run(&self, context: &mut PipelineExecutionContext) -> ActionResult;
 
-    fn format_input(text: &str, input: PathBuf) -> Cow<str> {
-        lazy_static! {
-            static ref INPUT_RE: Regex = Regex::new(r"(\$input\$)").unwrap();
-        }
-        INPUT_RE.replace_all(text, input.to_string_lossy())
+    // Checks the action's configuration once, when the handler is started. Failures keep the handler from starting.
+    fn validate(&self) -> Result<(), ActionError> {
+        Ok(())
+    }
+
+    // Replaces `$placeholder$` keywords with values of the input file & context. See `placeholders::format`
+    fn format_placeholders<F>(
+        text: &str,
+        input: Option<&Path>,
+        context: &PipelineExecutionContext,
+        quote: F,
+    ) -> String
+    where
+        F: Fn(&str) -> String,
+    {
+        placeholders::format(text, input, context, quote)
     }
 
     fn format_datetime<S>(text: S) -> String
@@ -42,7 +69,14 @@ pub fn construct_working_dir(input_path: &Path, directory_path: &Path) -> PathBu
 #[serde(tag = "type")]
 pub enum PipelineActions {
     MoveToDir(MoveToDir),
-    RunCmd(RunCmd),
+    RunCmd(Box<RunCmd>), // Boxed, as it's much larger than the other actions
+    RenameFile(RenameFile),
+    DeleteFile(DeleteFile),
+    MoveToTrash(MoveToTrash),
+    Compress(Compress),
+    Extract(Extract),
+    Checksum(Checksum),
+    Deduplicate(Deduplicate),
 }
 
 impl PipelineActions {
@@ -52,25 +86,81 @@ impl PipelineActions {
     {
         actions
             .map(|action_name| match action_name.to_lowercase().as_str() {
-                "runcmd" => Self::RunCmd(RunCmd::default()),
+                "runcmd" => Self::RunCmd(Box::default()),
                 "movetodir" => Self::MoveToDir(MoveToDir::default()),
+                "renamefile" => Self::RenameFile(RenameFile::default()),
+                "deletefile" => Self::DeleteFile(DeleteFile::default()),
+                "movetotrash" => Self::MoveToTrash(MoveToTrash::default()),
+                "compress" => Self::Compress(Compress::default()),
+                "extract" => Self::Extract(Extract::default()),
+                "checksum" => Self::Checksum(Checksum::default()),
+                "deduplicate" => Self::Deduplicate(Deduplicate::default()),
                 _ => panic!("Incompatible action provided"),
             })
             .collect()
     }
+
+    pub fn when(&self) -> Option<&str> {
+        match self {
+            PipelineActions::MoveToDir(action) => action.when.as_deref(),
+            PipelineActions::RunCmd(action) => action.when.as_deref(),
+            PipelineActions::RenameFile(action) => action.when.as_deref(),
+            PipelineActions::DeleteFile(action) => action.when.as_deref(),
+            PipelineActions::MoveToTrash(action) => action.when.as_deref(),
+            PipelineActions::Compress(action) => action.when.as_deref(),
+            PipelineActions::Extract(action) => action.when.as_deref(),
+            PipelineActions::Checksum(action) => action.when.as_deref(),
+            PipelineActions::Deduplicate(action) => action.when.as_deref(),
+        }
+    }
+
+    pub fn retry_policy(&self) -> Option<&RetryPolicy> {
+        match self {
+            PipelineActions::MoveToDir(action) => action.retry.as_ref(),
+            PipelineActions::RunCmd(action) => action.retry.as_ref(),
+            PipelineActions::RenameFile(action) => action.retry.as_ref(),
+            PipelineActions::DeleteFile(action) => action.retry.as_ref(),
+            PipelineActions::MoveToTrash(action) => action.retry.as_ref(),
+            PipelineActions::Compress(action) => action.retry.as_ref(),
+            PipelineActions::Extract(action) => action.retry.as_ref(),
+            PipelineActions::Checksum(action) => action.retry.as_ref(),
+            PipelineActions::Deduplicate(action) => action.retry.as_ref(),
+        }
+    }
 }
 
 impl PipelineAction for PipelineActions {
-    fn run(&self, context: &mut PipelineExecutionContext) -> bool {
+    fn run(&self, context: &mut PipelineExecutionContext) -> ActionResult {
         match self {
             PipelineActions::MoveToDir(action) => action.run(context),
             PipelineActions::RunCmd(action) => action.run(context),
+            PipelineActions::RenameFile(action) => action.run(context),
+            PipelineActions::DeleteFile(action) => action.run(context),
+            PipelineActions::MoveToTrash(action) => action.run(context),
+            PipelineActions::Compress(action) => action.run(context),
+            PipelineActions::Extract(action) => action.run(context),
+            PipelineActions::Checksum(action) => action.run(context),
+            PipelineActions::Deduplicate(action) => action.run(context),
+        }
+    }
+
+    fn validate(&self) -> Result<(), ActionError> {
+        match self {
+            PipelineActions::MoveToDir(action) => action.validate(),
+            PipelineActions::RunCmd(action) => action.validate(),
+            PipelineActions::RenameFile(action) => action.validate(),
+            PipelineActions::DeleteFile(action) => action.validate(),
+            PipelineActions::MoveToTrash(action) => action.validate(),
+            PipelineActions::Compress(action) => action.validate(),
+            PipelineActions::Extract(action) => action.validate(),
+            PipelineActions::Checksum(action) => action.validate(),
+            PipelineActions::Deduplicate(action) => action.validate(),
         }
     }
 }
 
 impl Default for PipelineActions {
     fn default() -> Self {
-        Self::RunCmd(RunCmd::default())
+        Self::RunCmd(Box::default())
     }
 }
@@ -1,13 +1,16 @@
 use std::{
     ffi::OsStr,
-    fs,
     io::ErrorKind,
     path::{Path, PathBuf},
 };
 
 use serde::{Deserialize, Serialize};
 
-use super::{construct_working_dir, PipelineAction};
+use super::{
+    conflict_strategy::{ConflictResolution, ConflictStrategy},
+    construct_working_dir, file_operations, ActionError, ActionErrorKind, ActionOutcome,
+    ActionResult, PipelineAction, RetryPolicy,
+};
 use crate::{
     pipeline_context_input::PipelineContextInput,
     pipeline_execution_context::PipelineExecutionContext,
@@ -18,26 +21,41 @@ pub struct MoveToDir {
     pub input: PipelineContextInput,
     pub directory_path: PathBuf,
     pub requires_directory_exists: bool,
-    pub replace_older_files: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replace_older_files: Option<bool>, // Legacy - Same as `on_conflict` overwrite / fail
+    pub on_conflict: Option<ConflictStrategy>, // Defaults to fail
     pub keep_input_file_intact: bool,
+    #[serde(default)]
+    pub input_formatting: bool, // Replaces placeholders in `directory_path`. Off by default, so existing `$` in paths are kept
     pub datetime_formatting: bool,
+    #[serde(default)]
+    pub preserve_metadata: bool, // Copied files keep the original's timestamps & extended attributes
+    pub when: Option<String>, // Condition deciding if the action is executed
+    pub retry: Option<RetryPolicy>,
 }
 
 impl MoveToDir {
+    fn conflict_strategy(&self) -> ConflictStrategy {
+        self.on_conflict.unwrap_or_else(|| {
+            ConflictStrategy::from_replace_older_files(self.replace_older_files.unwrap_or(false))
+        })
+    }
+
     fn ensure_dir_exists(
         &self,
         context: &mut PipelineExecutionContext,
         working_dir_path: &Path,
-    ) -> bool {
+    ) -> Result<(), ActionError> {
         if !working_dir_path.is_dir() {
             if self.requires_directory_exists {
-                return context.handle_error("Directory required to exist");
-            } else {
-                fs::create_dir_all(&working_dir_path).unwrap();
-                return true;
+                return Err(ActionError::new(
+                    ActionErrorKind::Precondition,
+                    "Directory required to exist",
+                ));
             }
+            file_operations::create_dir_all(context, working_dir_path)?;
         }
-        true
+        Ok(())
     }
 
     fn apply(
@@ -46,35 +64,43 @@ impl MoveToDir {
         working_dir_path: &Path,
         input_path: &Path,
         input_file_name: &OsStr,
-    ) -> bool {
-        if !self.ensure_dir_exists(context, working_dir_path) {
-            return false;
-        }
+    ) -> ActionResult {
+        self.ensure_dir_exists(context, working_dir_path)?;
         let mut new_file_path = PathBuf::from(working_dir_path);
         new_file_path.push(input_file_name);
-        if new_file_path.is_file() && !self.replace_older_files {
-            context.handle_error("Can't replace older file")
+        if new_file_path == input_path {
+            return Ok(ActionOutcome::Skipped(String::from(
+                "File already in directory",
+            )));
+        }
+        let new_file_path = match self
+            .conflict_strategy()
+            .resolve(input_path, new_file_path)?
+        {
+            ConflictResolution::Write(new_file_path) => new_file_path,
+            ConflictResolution::Skip(reason) => return Ok(ActionOutcome::Skipped(reason)),
+        };
+        context.mark_written(&new_file_path);
+        if self.keep_input_file_intact {
+            file_operations::copy_atomically(
+                context,
+                input_path,
+                &new_file_path,
+                self.preserve_metadata,
+            )?;
+            context.log("Copied file");
         } else {
-            match fs::copy(&input_path, &new_file_path) {
-                Ok(_) => {
-                    context.log("Copied file");
-                    if self.keep_input_file_intact {
-                        context.action_file_path = Some(new_file_path);
-                        true
-                    } else {
-                        match fs::remove_file(input_path) {
-                            Ok(_) => {
-                                context.log("Deleted original file");
-                                context.action_file_path = Some(new_file_path);
-                                true
-                            }
-                            Err(err) => context.handle_error(format!("{}", err)),
-                        }
-                    }
-                }
-                Err(err) => context.handle_error(format!("{:?}", err)),
-            }
+            context.mark_written(input_path);
+            file_operations::move_file(
+                context,
+                input_path,
+                &new_file_path,
+                self.preserve_metadata,
+            )?;
+            context.log("Moved file");
         }
+        context.action_file_path = Some(new_file_path);
+        Ok(ActionOutcome::Completed)
     }
 }
 
@@ -84,50 +110,57 @@ impl Default for MoveToDir {
             input: PipelineContextInput::EventFilePath,
             directory_path: PathBuf::from("output_dir_path"),
             requires_directory_exists: false,
-            replace_older_files: true,
+            replace_older_files: None,
+            on_conflict: Some(ConflictStrategy::Overwrite),
             keep_input_file_intact: false,
+            input_formatting: false,
             datetime_formatting: true,
+            preserve_metadata: false,
+            when: None,
+            retry: None,
         }
     }
 }
 
 impl PipelineAction for MoveToDir {
-    fn run(&self, context: &mut PipelineExecutionContext) -> bool {
-        match context.get_input(self.input) {
-            Some(input_path) => match input_path.file_name() {
-                Some(input_file_name) => {
-                    let output_directory_path = if self.datetime_formatting {
-                        PathBuf::from(Self::format_datetime(
-                            &self.directory_path.to_string_lossy(),
-                        ))
-                    } else {
-                        self.directory_path.to_path_buf()
-                    };
-                    let working_dir_path =
-                        construct_working_dir(&input_path, &output_directory_path);
-                    match working_dir_path.canonicalize() {
-                        Ok(working_dir_path) => {
-                            self.apply(context, &working_dir_path, &input_path, input_file_name)
-                        }
-                        Err(err) => match err.kind() {
-                            ErrorKind::NotFound => {
-                                if !self.ensure_dir_exists(context, &working_dir_path) {
-                                    return false;
-                                }
-                                self.apply(
-                                    context,
-                                    &working_dir_path.canonicalize().unwrap(),
-                                    &input_path,
-                                    input_file_name,
-                                )
-                            }
-                            _ => context.handle_error(format!("{:?}", err)),
-                        },
-                    }
-                }
-                None => context.handle_error("Path can't be parsed as file"),
-            },
-            None => context.handle_error("Input doesn't contain value"),
+    fn run(&self, context: &mut PipelineExecutionContext) -> ActionResult {
+        let input_path = context.get_input(self.input).ok_or_else(|| {
+            ActionError::new(ActionErrorKind::Precondition, "Input doesn't contain value")
+        })?;
+        let input_file_name = input_path.file_name().ok_or_else(|| {
+            ActionError::new(
+                ActionErrorKind::Precondition,
+                "Path can't be parsed as file",
+            )
+        })?;
+        let mut output_directory_path = self.directory_path.to_string_lossy().to_string();
+        if self.datetime_formatting {
+            output_directory_path = Self::format_datetime(output_directory_path);
+        }
+        if self.input_formatting {
+            output_directory_path = Self::format_placeholders(
+                &output_directory_path,
+                Some(&input_path),
+                context,
+                str::to_owned,
+            );
+        }
+        let output_directory_path = PathBuf::from(output_directory_path);
+        let working_dir_path = construct_working_dir(&input_path, &output_directory_path);
+        match working_dir_path.canonicalize() {
+            Ok(working_dir_path) => {
+                self.apply(context, &working_dir_path, &input_path, input_file_name)
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                self.ensure_dir_exists(context, &working_dir_path)?;
+                self.apply(
+                    context,
+                    &working_dir_path.canonicalize()?,
+                    &input_path,
+                    input_file_name,
+                )
+            }
+            Err(err) => Err(err.into()),
         }
     }
 }
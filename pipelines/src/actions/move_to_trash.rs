@@ -0,0 +1,243 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    file_operations, ActionError, ActionErrorKind, ActionOutcome, ActionResult, PipelineAction,
+    RetryPolicy,
+};
+use crate::{
+    pipeline_context_input::PipelineContextInput,
+    pipeline_execution_context::PipelineExecutionContext,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MoveToTrash {
+    pub input: PipelineContextInput,
+    #[serde(default)]
+    pub allow_outside_handler_dir: bool,
+    pub when: Option<String>, // Condition deciding if the action is executed
+    pub retry: Option<RetryPolicy>,
+}
+
+impl Default for MoveToTrash {
+    fn default() -> Self {
+        Self {
+            input: PipelineContextInput::EventFilePath,
+            allow_outside_handler_dir: false,
+            when: None,
+            retry: None,
+        }
+    }
+}
+
+impl PipelineAction for MoveToTrash {
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    fn validate(&self) -> Result<(), ActionError> {
+        Err(ActionError::new(
+            ActionErrorKind::Config,
+            "MoveToTrash is only supported on Linux & BSD (freedesktop.org trash)",
+        ))
+    }
+
+    fn run(&self, context: &mut PipelineExecutionContext) -> ActionResult {
+        let input_path = context.get_input(self.input).ok_or_else(|| {
+            ActionError::new(ActionErrorKind::Precondition, "Input doesn't contain value")
+        })?;
+        fs::symlink_metadata(&input_path)?;
+        file_operations::ensure_inside_handler_dir(
+            context,
+            &input_path,
+            self.allow_outside_handler_dir,
+        )?;
+        context.mark_written(&input_path);
+        let trashed_file_path = trash_file(&input_path)?;
+        context.mark_written(&trashed_file_path); // In case the trash is within the handler's directory
+        context.log(format!("Moved file to trash - {:?}", trashed_file_path));
+        context.action_file_path = Some(trashed_file_path);
+        Ok(ActionOutcome::Completed)
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+use self::freedesktop::trash_file;
+
+// Not reached, as the action fails validation on other platforms
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn trash_file(_file_path: &std::path::Path) -> Result<std::path::PathBuf, ActionError> {
+    Err(ActionError::new(
+        ActionErrorKind::Precondition,
+        "MoveToTrash is only supported on Linux & BSD (freedesktop.org trash)",
+    ))
+}
+
+// Trash as specified by https://specifications.freedesktop.org/trash-spec/trashspec-latest.html -
+// Trashed files are moved to the trash's `files` directory, with a `.trashinfo` file in its `info` directory
+// recording their original path, so they can be restored by file managers
+#[cfg(all(unix, not(target_os = "macos")))]
+mod freedesktop {
+    use std::{
+        env,
+        ffi::OsString,
+        fs::{self, DirBuilder, OpenOptions},
+        io::{ErrorKind, Write},
+        os::unix::{
+            ffi::OsStrExt,
+            fs::{DirBuilderExt, MetadataExt, PermissionsExt},
+        },
+        path::{Path, PathBuf},
+    };
+
+    use crate::actions::{ActionError, ActionErrorKind};
+
+    const STICKY_BIT: u32 = 0o1000;
+    const MAX_NAME_ATTEMPTS: u32 = 10_000;
+
+    pub fn trash_file(file_path: &Path) -> Result<PathBuf, ActionError> {
+        let file_path = match (file_path.parent(), file_path.file_name()) {
+            (Some(parent_dir_path), Some(file_name)) => {
+                parent_dir_path.canonicalize()?.join(file_name)
+            }
+            _ => {
+                return Err(ActionError::new(
+                    ActionErrorKind::Precondition,
+                    "Path can't be parsed as file",
+                ))
+            }
+        };
+        let file_device = fs::symlink_metadata(&file_path)?.dev();
+        let home_trash_path = home_trash_path()?;
+        let (trash_path, info_path) = if device_of(&home_trash_path)? == file_device {
+            (home_trash_path, file_path.clone())
+        } else {
+            // Files are kept on their own device - Trashing shouldn't copy them
+            let top_dir_path = top_dir_path(&file_path, file_device)?;
+            let info_path = file_path.strip_prefix(&top_dir_path).unwrap().to_path_buf();
+            (top_dir_trash_path(&top_dir_path)?, info_path)
+        };
+        let files_dir_path = trash_path.join("files");
+        let info_dir_path = trash_path.join("info");
+        for dir_path in [&files_dir_path, &info_dir_path] {
+            DirBuilder::new()
+                .recursive(true)
+                .mode(0o700)
+                .create(dir_path)?;
+        }
+        let trash_info = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            percent_encode(&info_path),
+            chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+        );
+        let file_name = file_path.file_name().unwrap();
+        for attempt in 1..=MAX_NAME_ATTEMPTS {
+            let trashed_file_name = numbered_file_name(file_name, attempt);
+            let trashed_file_path = files_dir_path.join(&trashed_file_name);
+            let mut info_file_name = trashed_file_name.clone();
+            info_file_name.push(".trashinfo");
+            let info_file_path = info_dir_path.join(info_file_name);
+            if trashed_file_path.exists() {
+                continue;
+            }
+            // Creating the info file claims the name, as the spec requires
+            let mut info_file = match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&info_file_path)
+            {
+                Ok(info_file) => info_file,
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err.into()),
+            };
+            let result = info_file
+                .write_all(trash_info.as_bytes())
+                .and_then(|_| fs::rename(&file_path, &trashed_file_path));
+            if let Err(err) = result {
+                let _ = fs::remove_file(&info_file_path);
+                return Err(err.into());
+            }
+            return Ok(trashed_file_path);
+        }
+        Err(ActionError::new(
+            ActionErrorKind::Precondition,
+            "No available name in trash",
+        ))
+    }
+
+    // $XDG_DATA_HOME/Trash, defaulting to ~/.local/share/Trash. Services may run without either set
+    fn home_trash_path() -> Result<PathBuf, ActionError> {
+        let data_home_path = match env::var_os("XDG_DATA_HOME") {
+            Some(data_home) if !data_home.is_empty() => PathBuf::from(data_home),
+            _ => match env::var_os("HOME") {
+                Some(home) => PathBuf::from(home).join(".local").join("share"),
+                None => {
+                    return Err(ActionError::new(
+                        ActionErrorKind::Precondition,
+                        "Home trash requires HOME or XDG_DATA_HOME to be set",
+                    ))
+                }
+            },
+        };
+        Ok(data_home_path.join("Trash"))
+    }
+
+    // Device of the path, or of its closest existing ancestor
+    fn device_of(path: &Path) -> Result<u64, ActionError> {
+        path.ancestors()
+            .find_map(|path| fs::metadata(path).ok())
+            .map(|metadata| metadata.dev())
+            .ok_or_else(|| ActionError::new(ActionErrorKind::Io, "Trash path doesn't exist"))
+    }
+
+    // Mount point of the device containing the file
+    fn top_dir_path(file_path: &Path, file_device: u64) -> Result<PathBuf, ActionError> {
+        let mut top_dir_path = file_path.parent().unwrap();
+        while let Some(parent_dir_path) = top_dir_path.parent() {
+            if fs::metadata(parent_dir_path)?.dev() != file_device {
+                break;
+            }
+            top_dir_path = parent_dir_path;
+        }
+        Ok(top_dir_path.to_path_buf())
+    }
+
+    // $topdir/.Trash/$uid if an administrator created a shared, sticky $topdir/.Trash. Otherwise $topdir/.Trash-$uid
+    fn top_dir_trash_path(top_dir_path: &Path) -> Result<PathBuf, ActionError> {
+        let uid = unsafe { libc::geteuid() };
+        let shared_trash_path = top_dir_path.join(".Trash");
+        if let Ok(metadata) = fs::symlink_metadata(&shared_trash_path) {
+            if metadata.is_dir() && metadata.permissions().mode() & STICKY_BIT != 0 {
+                return Ok(shared_trash_path.join(uid.to_string()));
+            }
+        }
+        Ok(top_dir_path.join(format!(".Trash-{}", uid)))
+    }
+
+    // `name.ext`, `name.2.ext`, `name.3.ext`, ...
+    fn numbered_file_name(file_name: &std::ffi::OsStr, attempt: u32) -> OsString {
+        if attempt == 1 {
+            return file_name.to_os_string();
+        }
+        let file_path = Path::new(file_name);
+        let mut numbered_file_name = file_path.file_stem().unwrap_or(file_name).to_os_string();
+        numbered_file_name.push(format!(".{}", attempt));
+        if let Some(extension) = file_path.extension() {
+            numbered_file_name.push(".");
+            numbered_file_name.push(extension);
+        }
+        numbered_file_name
+    }
+
+    // Paths in `.trashinfo` files are URL escaped
+    fn percent_encode(path: &Path) -> String {
+        let mut encoded_path = String::new();
+        for &byte in path.as_os_str().as_bytes() {
+            match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                    encoded_path.push(byte as char)
+                }
+                _ => encoded_path.push_str(&format!("%{:02X}", byte)),
+            }
+        }
+        encoded_path
+    }
+}
@@ -0,0 +1,94 @@
+use std::{env, fs, path::Path};
+
+use crate::pipeline_execution_context::PipelineExecutionContext;
+
+// Replaces `$placeholder$` keywords in the text with values of the action's input file & the pipeline's context:
+//   $input$ - Input file path
+//   $name$, $stem$, $ext$, $parent$, $size$ - Input file's name, name without extension, extension, directory & size in bytes
+//   $relative$ - Input file path, relative to the handler's directory
+//   $handler_dir$ - Handler's directory
+//   $event$ - Event type that triggered the pipeline
+//   $error$ - Pipeline's failure, when executing on_failure & finally actions
+//   $env:VAR$ - Environment variable of foldend
+//   $variable$ - Value set by a previous action (Checksum's `$sha256$`, `$blake3$`, `$md5$`)
+// Values which aren't available are replaced with an empty string. `$$` is replaced with a literal `$`.
+// Unknown keywords are left untouched (Like shell variables - `$HOME`).
+// Values are passed through `quote`, for texts interpreted by a shell
+pub fn format<F>(
+    text: &str,
+    input: Option<&Path>,
+    context: &PipelineExecutionContext,
+    quote: F,
+) -> String
+where
+    F: Fn(&str) -> String,
+{
+    let mut formatted_text = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        formatted_text.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        if let Some(escaped_rest) = rest.strip_prefix('$') {
+            formatted_text.push('$');
+            rest = escaped_rest;
+            continue;
+        }
+        let value = rest
+            .find('$')
+            .and_then(|end| Some((end, resolve(&rest[..end], input, context)?)));
+        match value {
+            Some((end, value)) => {
+                formatted_text.push_str(&quote(&value));
+                rest = &rest[end + 1..];
+            }
+            None => formatted_text.push('$'),
+        }
+    }
+    formatted_text.push_str(rest);
+    formatted_text
+}
+
+fn resolve(
+    placeholder: &str,
+    input: Option<&Path>,
+    context: &PipelineExecutionContext,
+) -> Option<String> {
+    let path_value = |value: Option<&Path>| {
+        value
+            .map(|value| value.to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+    let os_str_value = |value: Option<&std::ffi::OsStr>| {
+        value
+            .map(|value| value.to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+    let value = match placeholder {
+        "input" => path_value(input),
+        "name" => os_str_value(input.and_then(Path::file_name)),
+        "stem" => os_str_value(input.and_then(Path::file_stem)),
+        "ext" => os_str_value(input.and_then(Path::extension)),
+        "parent" => path_value(input.and_then(Path::parent)),
+        "size" => input
+            .and_then(|input| fs::metadata(input).ok())
+            .map(|metadata| metadata.len().to_string())
+            .unwrap_or_default(),
+        "relative" => path_value(input.map(|input| {
+            input
+                .strip_prefix(&context.handler_directory_path)
+                .unwrap_or(input)
+        })),
+        "handler_dir" => path_value(Some(&context.handler_directory_path)),
+        "event" => context.event_type.clone().unwrap_or_default(),
+        "error" => context
+            .error
+            .as_ref()
+            .map(|error| error.to_string())
+            .unwrap_or_default(),
+        _ => match placeholder.strip_prefix("env:") {
+            Some(variable) if !variable.is_empty() => env::var(variable).unwrap_or_default(),
+            _ => return context.variables.get(placeholder).cloned(),
+        },
+    };
+    Some(value)
+}
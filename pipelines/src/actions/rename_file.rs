@@ -0,0 +1,155 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    conflict_strategy::{ConflictResolution, ConflictStrategy},
+    ActionError, ActionErrorKind, ActionOutcome, ActionResult, PipelineAction, RetryPolicy,
+};
+use crate::{
+    pipeline_context_input::PipelineContextInput,
+    pipeline_execution_context::PipelineExecutionContext,
+};
+
+const COUNTER_KEYWORD: &str = "$counter$";
+const MAX_COUNTER: u32 = 100_000;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RenameFile {
+    pub input: PipelineContextInput,
+    pub template: String, // New file name. Supports placeholders, `$counter$` & `naming_regex_match` capture groups (`$1`, `${name}`)
+    pub on_conflict: Option<ConflictStrategy>, // Defaults to fail
+    pub datetime_formatting: bool,
+    pub when: Option<String>, // Condition deciding if the action is executed
+    pub retry: Option<RetryPolicy>,
+}
+
+impl RenameFile {
+    // Datetime formatting is applied first, then capture groups, `$counter$` & placeholders -
+    // So values of earlier stages aren't formatted again
+    fn format_file_name(
+        &self,
+        context: &PipelineExecutionContext,
+        input_path: &Path,
+        counter: Option<u32>,
+    ) -> String {
+        let mut file_name = self.template.to_owned();
+        if self.datetime_formatting {
+            file_name = Self::format_datetime(file_name);
+        }
+        file_name = format_captures(&file_name, context);
+        if let Some(counter) = counter {
+            file_name = file_name.replace(COUNTER_KEYWORD, &counter.to_string());
+        }
+        Self::format_placeholders(&file_name, Some(input_path), context, str::to_owned)
+    }
+
+    // Path of the renamed file. With `$counter$`, the smallest counter not naming an existing file
+    fn new_file_path(
+        &self,
+        context: &PipelineExecutionContext,
+        input_path: &Path,
+    ) -> Result<PathBuf, ActionError> {
+        let parent_dir_path = input_path.parent().unwrap();
+        if !self.template.contains(COUNTER_KEYWORD) {
+            let file_name = self.format_file_name(context, input_path, None);
+            return construct_file_path(parent_dir_path, &file_name);
+        }
+        for counter in 1..=MAX_COUNTER {
+            let file_name = self.format_file_name(context, input_path, Some(counter));
+            let new_file_path = construct_file_path(parent_dir_path, &file_name)?;
+            if new_file_path == input_path || !new_file_path.exists() {
+                return Ok(new_file_path);
+            }
+        }
+        Err(ActionError::new(
+            ActionErrorKind::Precondition,
+            "No available counter for file name",
+        ))
+    }
+}
+
+impl Default for RenameFile {
+    fn default() -> Self {
+        Self {
+            input: PipelineContextInput::EventFilePath,
+            template: String::from("%Y-%m-%d_$name$"),
+            on_conflict: None,
+            datetime_formatting: true,
+            when: None,
+            retry: None,
+        }
+    }
+}
+
+impl PipelineAction for RenameFile {
+    fn run(&self, context: &mut PipelineExecutionContext) -> ActionResult {
+        let input_path = context.get_input(self.input).ok_or_else(|| {
+            ActionError::new(ActionErrorKind::Precondition, "Input doesn't contain value")
+        })?;
+        let new_file_path = self.new_file_path(context, &input_path)?;
+        if new_file_path == input_path {
+            context.action_file_path = Some(new_file_path);
+            return Ok(ActionOutcome::Skipped(String::from("File already named")));
+        }
+        let new_file_path = match self
+            .on_conflict
+            .unwrap_or(ConflictStrategy::Fail)
+            .resolve(&input_path, new_file_path)?
+        {
+            ConflictResolution::Write(new_file_path) => new_file_path,
+            ConflictResolution::Skip(reason) => return Ok(ActionOutcome::Skipped(reason)),
+        };
+        fs::rename(&input_path, &new_file_path)?;
+        context.log(format!("Renamed file to {:?}", new_file_path));
+        context.mark_written(&input_path);
+        context.mark_written(&new_file_path);
+        context.action_file_path = Some(new_file_path);
+        Ok(ActionOutcome::Completed)
+    }
+}
+
+// Files are renamed within their directory
+fn construct_file_path(parent_dir_path: &Path, file_name: &str) -> Result<PathBuf, ActionError> {
+    if file_name.is_empty() || Path::new(file_name).file_name() != Some(file_name.as_ref()) {
+        return Err(ActionError::new(
+            ActionErrorKind::Precondition,
+            format!("Formatted file name {:?} is invalid", file_name),
+        ));
+    }
+    Ok(parent_dir_path.join(file_name))
+}
+
+// Replaces `$1` / `${name}` with capture groups of `naming_regex_match` on the event's file path.
+// Captured values' `$` are escaped, so they aren't formatted as placeholders
+fn format_captures(text: &str, context: &PipelineExecutionContext) -> String {
+    lazy_static! {
+        static ref CAPTURE_RE: Regex = Regex::new(r"\$\$|\$(\d+)|\$\{(\w+)\}").unwrap();
+    }
+    let naming_regex = match &context.naming_regex {
+        Some(naming_regex) => naming_regex,
+        None => return text.to_owned(),
+    };
+    let event_file_path = context.event_file_path.to_string_lossy();
+    let captures = naming_regex.captures(&event_file_path);
+    let formatted_text = CAPTURE_RE.replace_all(text, |keyword: &Captures| {
+        let capture = match (keyword.get(1), keyword.get(2)) {
+            (Some(index), _) => index
+                .as_str()
+                .parse()
+                .ok()
+                .and_then(|index| captures.as_ref()?.get(index)),
+            (None, Some(name)) => captures.as_ref().and_then(|c| c.name(name.as_str())),
+            (None, None) => return keyword[0].to_owned(), // Escaped `$$` is kept for placeholders formatting
+        };
+        capture
+            .map(|capture| capture.as_str().replace('$', "$$"))
+            .unwrap_or_default()
+    });
+    formatted_text.to_string()
+}
@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+// Re-runs an action failing on retryable errors, doubling the delay between attempts
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    pub attempts: u32, // Including the first attempt
+    #[serde(default = "RetryPolicy::default_initial_delay_ms")]
+    pub initial_delay_ms: u64,
+    #[serde(default = "RetryPolicy::default_max_delay_ms")]
+    pub max_delay_ms: u64,
+}
+
+impl RetryPolicy {
+    fn default_initial_delay_ms() -> u64 {
+        500
+    }
+
+    fn default_max_delay_ms() -> u64 {
+        30000
+    }
+
+    // Delay before the attempt following the given (1 based) failed attempt
+    pub fn delay(&self, failed_attempt: u32) -> Duration {
+        let exponent = failed_attempt.saturating_sub(1).min(63);
+        let delay_ms = self
+            .initial_delay_ms
+            .saturating_mul(1u64 << exponent)
+            .min(self.max_delay_ms);
+        Duration::from_millis(delay_ms)
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    path::PathBuf,
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+mod process;
+mod restrictions;
+mod shell;
+pub use self::restrictions::ResourceLimits;
+pub use self::shell::Shell;
+
+use super::{
+    construct_working_dir, ActionError, ActionErrorKind, ActionOutcome, ActionResult,
+    PipelineAction, RetryPolicy,
+};
+use crate::{
+    pipeline_context_input::PipelineContextInput,
+    pipeline_execution_context::PipelineExecutionContext,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunCmd {
+    pub input: PipelineContextInput,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub command: String, // Executed through `shell`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>, // Program & its arguments, executed directly without a shell. Used instead of `command`
+    pub shell: Option<Shell>, // Defaults to `cmd` on Windows, `sh` elsewhere
+    pub input_formatting: bool,
+    pub datetime_formatting: bool,
+    #[serde(default = "RunCmd::default_success_exit_codes")]
+    pub success_exit_codes: Vec<i32>, // Exit codes the command is considered successful with
+    pub working_dir: Option<PathBuf>, // Relative to the event file's directory. Defaults to it
+    #[serde(default)]
+    pub pipe_input_to_stdin: bool, // Input file's contents are written to the command's stdin
+    pub timeout_secs: Option<u64>,    // Command's process group is terminated once expired
+    pub run_as_user: Option<String>,  // User name or ID the command runs as (Unix only)
+    pub run_as_group: Option<String>, // Group name or ID the command runs as. Defaults to the user's primary group (Unix only)
+    pub when: Option<String>,         // Condition deciding if the action is executed
+    pub retry: Option<RetryPolicy>,
+    pub limits: Option<ResourceLimits>, // Resource limits of the command (Unix only)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>, // Additional environment variables. Values are formatted like the command
+}
+
+// Output of the last command executed by the pipeline
+#[derive(Clone, Debug, Default)]
+pub struct CommandOutput {
+    pub exit_code: Option<i32>, // None if the command was terminated by a signal
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl RunCmd {
+    fn default_success_exit_codes() -> Vec<i32> {
+        vec![0]
+    }
+
+    // Datetime formatting is applied first, so placeholder values (File paths, etc.) are left untouched.
+    // Placeholder values are quoted for `shell` if given
+    fn format_text(
+        &self,
+        text: &str,
+        context: &PipelineExecutionContext,
+        shell: Option<Shell>,
+    ) -> String {
+        let mut formatted_text = text.to_owned();
+        if self.datetime_formatting {
+            formatted_text = Self::format_datetime(formatted_text);
+        }
+        if self.input_formatting {
+            let input_path = context.get_input(self.input);
+            formatted_text = Self::format_placeholders(
+                &formatted_text,
+                input_path.as_deref(),
+                context,
+                |value| match shell {
+                    Some(shell) => shell.quote(value),
+                    None => value.to_owned(),
+                },
+            );
+        }
+        formatted_text
+    }
+
+    fn build_command(&self, context: &PipelineExecutionContext) -> Command {
+        match self.args.split_first() {
+            Some((program, args)) => {
+                let mut process = Command::new(self.format_text(program, context, None));
+                process.args(args.iter().map(|arg| self.format_text(arg, context, None)));
+                process
+            }
+            None => {
+                let shell = self.shell.unwrap_or_else(Shell::platform_default);
+                shell.command(&self.format_text(&self.command, context, Some(shell)))
+            }
+        }
+    }
+
+    // Sets the command's working directory, environment & stdin
+    fn prepare_command(
+        &self,
+        command: &mut Command,
+        context: &PipelineExecutionContext,
+    ) -> Result<(), ActionError> {
+        let working_dir_path = match &self.working_dir {
+            Some(working_dir) => construct_working_dir(&context.event_file_path, working_dir),
+            None => context.event_file_path.parent().unwrap().to_path_buf(),
+        };
+        command
+            .current_dir(working_dir_path)
+            .env("FOLDEN_EVENT_PATH", &context.event_file_path)
+            .env("FOLDEN_HANDLER_DIR", &context.handler_directory_path);
+        if let Some(action_file_path) = &context.action_file_path {
+            command.env("FOLDEN_ACTION_PATH", action_file_path);
+        }
+        if let Some(event_type) = &context.event_type {
+            command.env("FOLDEN_EVENT_KIND", event_type);
+        }
+        for (key, value) in &self.env {
+            command.env(key, self.format_text(value, context, None));
+        }
+        if self.pipe_input_to_stdin {
+            let input_path = context.get_input(self.input).ok_or_else(|| {
+                ActionError::new(ActionErrorKind::Precondition, "Input file path missing")
+            })?;
+            command.stdin(Stdio::from(File::open(input_path)?));
+        } else {
+            command.stdin(Stdio::null());
+        }
+        restrictions::apply(
+            command,
+            self.run_as_user.as_deref(),
+            self.run_as_group.as_deref(),
+            self.limits.as_ref(),
+        )
+    }
+}
+
+impl PipelineAction for RunCmd {
+    fn validate(&self) -> Result<(), ActionError> {
+        match (self.command.is_empty(), self.args.is_empty()) {
+            (true, true) => Err(ActionError::new(
+                ActionErrorKind::Config,
+                "Either command or args are required",
+            )),
+            (false, false) => Err(ActionError::new(
+                ActionErrorKind::Config,
+                "Only one of command or args can be set",
+            )),
+            _ => restrictions::validate(
+                self.run_as_user.as_deref(),
+                self.run_as_group.as_deref(),
+                self.limits.as_ref(),
+            ),
+        }
+    }
+
+    fn run(&self, context: &mut PipelineExecutionContext) -> ActionResult {
+        let mut command = self.build_command(context);
+        self.prepare_command(&mut command, context)?;
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let child = process::spawn(&mut command).map_err(|e| {
+            ActionError::new(
+                ActionErrorKind::Spawn,
+                format!(
+                    "Could not spawn command.\nCommand: {:?}\nError: {:?}",
+                    command, e
+                ),
+            )
+        })?;
+        let timeout = self.timeout_secs.map(Duration::from_secs);
+        let out = process::wait_with_output(child, timeout, |stream, line| {
+            context.log(format!("{} - {}", stream, line))
+        })?;
+        let output = CommandOutput {
+            exit_code: out.status.and_then(|status| status.code()),
+            stdout: String::from_utf8_lossy(&out.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&out.stderr).to_string(),
+        };
+        let result = match output.exit_code {
+            None if out.status.is_none() => Err(ActionError::new(
+                ActionErrorKind::Timeout,
+                format!("Timed out after {}s", self.timeout_secs.unwrap_or_default()),
+            )),
+            Some(exit_code) if self.success_exit_codes.contains(&exit_code) => {
+                Ok(ActionOutcome::Completed)
+            }
+            Some(exit_code) => Err(ActionError::new(
+                ActionErrorKind::Command,
+                format!("Exited with code {}", exit_code),
+            )),
+            None => Err(ActionError::new(
+                ActionErrorKind::Command,
+                "Terminated by a signal",
+            )),
+        };
+        context.command_output = Some(output);
+        result
+    }
+}
+
+impl Default for RunCmd {
+    fn default() -> Self {
+        Self {
+            input: PipelineContextInput::EventFilePath,
+            command: String::from("echo $input$"),
+            args: Vec::new(),
+            shell: None,
+            input_formatting: true,
+            datetime_formatting: true,
+            success_exit_codes: Self::default_success_exit_codes(),
+            working_dir: None,
+            pipe_input_to_stdin: false,
+            timeout_secs: None,
+            run_as_user: None,
+            run_as_group: None,
+            when: None,
+            retry: None,
+            limits: None,
+            env: BTreeMap::new(),
+        }
+    }
+}
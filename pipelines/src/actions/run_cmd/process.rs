@@ -0,0 +1,180 @@
+use std::{
+    io::{self, BufRead, BufReader, Read},
+    process::{Child, Command, ExitStatus},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam::channel::{self, RecvTimeoutError, Sender};
+use strum_macros::Display;
+
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(5); // Time given to the process group to exit after SIGTERM, before SIGKILL
+const OUTPUT_DRAIN_PERIOD: Duration = Duration::from_secs(2); // Time the output is still read after the process exited, or was killed
+
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+pub struct ProcessOutput {
+    pub status: Option<ExitStatus>, // None if the process timed out
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+// Starts the command in its own process group, so it can be terminated along with any process it started
+pub fn spawn(command: &mut Command) -> io::Result<Child> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    command.spawn()
+}
+
+// Waits for the process to exit, terminating it once the timeout expires.
+// Output lines are passed to `on_line` as they are produced, and are kept in full - Even on timeout.
+// Processes left in the background may keep the pipes open - Once the process exited, they're read for a short period, then left running
+pub fn wait_with_output<F>(
+    mut child: Child,
+    timeout: Option<Duration>,
+    mut on_line: F,
+) -> io::Result<ProcessOutput>
+where
+    F: FnMut(OutputStream, &str),
+{
+    let (lines_tx, lines_rx) = channel::unbounded();
+    if let Some(pipe) = child.stdout.take() {
+        spawn_reader(pipe, OutputStream::Stdout, lines_tx.clone());
+    }
+    if let Some(pipe) = child.stderr.take() {
+        spawn_reader(pipe, OutputStream::Stderr, lines_tx);
+    }
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let mut output = ProcessOutput {
+        status: None,
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+    };
+    let mut drain_deadline = None; // Set once the process exited
+    let mut readers_done = false;
+    let mut killed = false;
+    loop {
+        if !readers_done {
+            match lines_rx.recv_timeout(WAIT_POLL_INTERVAL) {
+                Ok((stream, line)) => output.push_line(stream, line, &mut on_line),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => readers_done = true,
+            }
+        } else {
+            thread::sleep(WAIT_POLL_INTERVAL);
+        }
+        if output.status.is_none() {
+            output.status = child.try_wait()?;
+            if output.status.is_some() {
+                drain_deadline = Some(Instant::now() + OUTPUT_DRAIN_PERIOD);
+            }
+        }
+        if readers_done && output.status.is_some() {
+            break;
+        }
+        let now = Instant::now();
+        if output.status.is_none() && matches!(deadline, Some(deadline) if now >= deadline) {
+            terminate(&mut child)?;
+            killed = true;
+            break;
+        }
+        if matches!(drain_deadline, Some(drain_deadline) if now >= drain_deadline) {
+            // Processes left in the background still hold the pipes. Their readers are detached rather than joined
+            break;
+        }
+    }
+    // Lines still buffered in the pipes of the killed processes
+    let lines_deadline = if killed {
+        Instant::now() + OUTPUT_DRAIN_PERIOD
+    } else {
+        Instant::now()
+    };
+    while let Ok((stream, line)) = lines_rx.recv_deadline(lines_deadline) {
+        output.push_line(stream, line, &mut on_line);
+    }
+    Ok(output)
+}
+
+impl ProcessOutput {
+    fn push_line<F>(&mut self, stream: OutputStream, line: Vec<u8>, on_line: &mut F)
+    where
+        F: FnMut(OutputStream, &str),
+    {
+        let text = String::from_utf8_lossy(&line);
+        on_line(stream, text.trim_end_matches(&['\r', '\n'][..]));
+        match stream {
+            OutputStream::Stdout => self.stdout.extend_from_slice(&line),
+            OutputStream::Stderr => self.stderr.extend_from_slice(&line),
+        }
+    }
+}
+
+// Reads the pipe line by line. The thread ends once the pipe is closed
+fn spawn_reader<R>(pipe: R, stream: OutputStream, lines_tx: Sender<(OutputStream, Vec<u8>)>)
+where
+    R: Read + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut pipe = BufReader::new(pipe);
+        loop {
+            let mut line = Vec::new();
+            match pipe.read_until(b'\n', &mut line) {
+                Ok(read) if read > 0 => {
+                    if lines_tx.send((stream, line)).is_err() {
+                        break;
+                    }
+                }
+                _ => break,
+            }
+        }
+    });
+}
+
+fn wait_until(child: &mut Child, deadline: Instant) -> io::Result<Option<ExitStatus>> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(None);
+        }
+        thread::sleep(WAIT_POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+// Sends SIGTERM to the process group, and SIGKILL if it's still running after the grace period
+#[cfg(unix)]
+fn terminate(child: &mut Child) -> io::Result<()> {
+    let process_group = -(child.id() as libc::pid_t);
+    unsafe { libc::kill(process_group, libc::SIGTERM) };
+    let exited = wait_until(child, Instant::now() + KILL_GRACE_PERIOD)?.is_some();
+    // Processes left in the group are killed even if the main process already exited
+    kill_process_group(child);
+    if !exited {
+        child.wait()?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn terminate(child: &mut Child) -> io::Result<()> {
+    child.kill()?;
+    child.wait()?;
+    Ok(())
+}
+
+// Kills any process left in the group
+#[cfg(unix)]
+fn kill_process_group(child: &Child) {
+    let process_group = -(child.id() as libc::pid_t);
+    unsafe { libc::kill(process_group, libc::SIGKILL) };
+}
@@ -0,0 +1,222 @@
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use crate::actions::ActionError;
+
+// Resource limits of the command's process, applied before it executes
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    pub cpu_secs: Option<u64>,     // CPU time
+    pub memory_bytes: Option<u64>, // Virtual memory size
+    pub open_files: Option<u64>,   // Open file descriptors
+    pub processes: Option<u64>,    // Processes of the user the command runs as
+}
+
+// Runs the command as the given user / group (Names or numeric IDs) and with the given limits.
+// If only the user is given, runs as the user's primary group
+#[cfg(unix)]
+pub fn apply(
+    command: &mut Command,
+    run_as_user: Option<&str>,
+    run_as_group: Option<&str>,
+    limits: Option<&ResourceLimits>,
+) -> Result<(), ActionError> {
+    use std::os::unix::process::CommandExt;
+
+    let (uid, gid) = resolve_ids(run_as_user, run_as_group)?;
+    if let Some(uid) = uid {
+        command.uid(uid);
+    }
+    if let Some(gid) = gid {
+        command.gid(gid);
+    }
+    if let Some(limits) = limits.cloned() {
+        // Executed in the forked child before exec, after switching user
+        unsafe {
+            command.pre_exec(move || unix::set_limits(&limits));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+pub fn validate(
+    run_as_user: Option<&str>,
+    run_as_group: Option<&str>,
+    _limits: Option<&ResourceLimits>,
+) -> Result<(), ActionError> {
+    resolve_ids(run_as_user, run_as_group).map(|_| ())
+}
+
+// The group is never left unset when running as another user - foldend's own group may be privileged (root)
+#[cfg(unix)]
+fn resolve_ids(
+    run_as_user: Option<&str>,
+    run_as_group: Option<&str>,
+) -> Result<(Option<u32>, Option<u32>), ActionError> {
+    use crate::actions::ActionErrorKind;
+
+    let user_ids = run_as_user.map(unix::lookup_user).transpose()?;
+    let gid = match run_as_group {
+        Some(group) => Some(unix::lookup_group(group)?),
+        None => user_ids.and_then(|(_uid, primary_gid)| primary_gid),
+    };
+    match (user_ids, gid) {
+        (Some(_), None) => Err(ActionError::new(
+            ActionErrorKind::Config,
+            format!(
+                "User {:?} has no primary group, run_as_group is required",
+                run_as_user.unwrap_or_default()
+            ),
+        )),
+        (user_ids, gid) => Ok((user_ids.map(|(uid, _primary_gid)| uid), gid)),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn apply(
+    _command: &mut Command,
+    run_as_user: Option<&str>,
+    run_as_group: Option<&str>,
+    limits: Option<&ResourceLimits>,
+) -> Result<(), ActionError> {
+    validate(run_as_user, run_as_group, limits)
+}
+
+#[cfg(not(unix))]
+pub fn validate(
+    run_as_user: Option<&str>,
+    run_as_group: Option<&str>,
+    limits: Option<&ResourceLimits>,
+) -> Result<(), ActionError> {
+    use crate::actions::ActionErrorKind;
+
+    if run_as_user.is_some() || run_as_group.is_some() || limits.is_some() {
+        return Err(ActionError::new(
+            ActionErrorKind::Config,
+            "run_as_user, run_as_group & limits are only supported on Unix",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::{ffi::CString, io, mem, ptr};
+
+    use super::ResourceLimits;
+    use crate::actions::{ActionError, ActionErrorKind};
+
+    const INITIAL_BUFFER_SIZE: usize = 1024;
+    const MAX_BUFFER_SIZE: usize = 1024 * 1024;
+
+    fn config_error(message: String) -> ActionError {
+        ActionError::new(ActionErrorKind::Config, message)
+    }
+
+    // Returns the user's ID & primary group ID
+    pub fn lookup_user(user: &str) -> Result<(u32, Option<u32>), ActionError> {
+        let mut buffer = vec![0 as libc::c_char; INITIAL_BUFFER_SIZE];
+        loop {
+            let mut passwd: libc::passwd = unsafe { mem::zeroed() };
+            let mut result = ptr::null_mut();
+            let code = match user.parse::<libc::uid_t>() {
+                Ok(uid) => unsafe {
+                    libc::getpwuid_r(
+                        uid,
+                        &mut passwd,
+                        buffer.as_mut_ptr(),
+                        buffer.len(),
+                        &mut result,
+                    )
+                },
+                Err(_) => {
+                    let name = CString::new(user)
+                        .map_err(|_| config_error(format!("Invalid user {:?}", user)))?;
+                    unsafe {
+                        libc::getpwnam_r(
+                            name.as_ptr(),
+                            &mut passwd,
+                            buffer.as_mut_ptr(),
+                            buffer.len(),
+                            &mut result,
+                        )
+                    }
+                }
+            };
+            if code == libc::ERANGE && buffer.len() < MAX_BUFFER_SIZE {
+                buffer.resize(buffer.len() * 2, 0);
+                continue;
+            }
+            if code != 0 {
+                return Err(io::Error::from_raw_os_error(code).into());
+            }
+            return match (result.is_null(), user.parse::<libc::uid_t>()) {
+                (false, _) => Ok((passwd.pw_uid, Some(passwd.pw_gid))),
+                (true, Ok(uid)) => Ok((uid, None)), // Numeric IDs don't require an existing user, but have no primary group
+                (true, Err(_)) => Err(config_error(format!("Unknown user {:?}", user))),
+            };
+        }
+    }
+
+    pub fn lookup_group(group: &str) -> Result<u32, ActionError> {
+        if let Ok(gid) = group.parse::<libc::gid_t>() {
+            return Ok(gid);
+        }
+        let name =
+            CString::new(group).map_err(|_| config_error(format!("Invalid group {:?}", group)))?;
+        let mut buffer = vec![0 as libc::c_char; INITIAL_BUFFER_SIZE];
+        loop {
+            let mut group_entry: libc::group = unsafe { mem::zeroed() };
+            let mut result = ptr::null_mut();
+            let code = unsafe {
+                libc::getgrnam_r(
+                    name.as_ptr(),
+                    &mut group_entry,
+                    buffer.as_mut_ptr(),
+                    buffer.len(),
+                    &mut result,
+                )
+            };
+            if code == libc::ERANGE && buffer.len() < MAX_BUFFER_SIZE {
+                buffer.resize(buffer.len() * 2, 0);
+                continue;
+            }
+            if code != 0 {
+                return Err(io::Error::from_raw_os_error(code).into());
+            }
+            if result.is_null() {
+                return Err(config_error(format!("Unknown group {:?}", group)));
+            }
+            return Ok(group_entry.gr_gid);
+        }
+    }
+
+    // Only async-signal-safe calls - Executed between fork & exec
+    pub fn set_limits(limits: &ResourceLimits) -> io::Result<()> {
+        set_limit(libc::RLIMIT_CPU, limits.cpu_secs)?;
+        set_limit(libc::RLIMIT_AS, limits.memory_bytes)?;
+        set_limit(libc::RLIMIT_NOFILE, limits.open_files)?;
+        set_limit(libc::RLIMIT_NPROC, limits.processes)?;
+        Ok(())
+    }
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    type Resource = libc::__rlimit_resource_t;
+    #[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+    type Resource = libc::c_int;
+
+    fn set_limit(resource: Resource, value: Option<u64>) -> io::Result<()> {
+        if let Some(value) = value {
+            let limit = libc::rlimit {
+                rlim_cur: value as libc::rlim_t,
+                rlim_max: value as libc::rlim_t,
+            };
+            if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
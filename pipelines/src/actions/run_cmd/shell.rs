@@ -0,0 +1,66 @@
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Shell {
+    Sh,
+    Bash,
+    Cmd,
+    Powershell,
+}
+
+impl Shell {
+    // Shell used when none is configured - `cmd` on Windows, `sh` elsewhere
+    pub fn platform_default() -> Self {
+        if cfg!(windows) {
+            Shell::Cmd
+        } else {
+            Shell::Sh
+        }
+    }
+
+    // Quotes a value substituted into a command, so it is passed as a single literal argument
+    pub fn quote(&self, value: &str) -> String {
+        match self {
+            Shell::Sh | Shell::Bash => format!("'{}'", value.replace('\'', r"'\''")),
+            // `cmd` expands `%VAR%` within quotes as well - `%` is escaped outside of them (`"a"^%"b"`)
+            Shell::Cmd => format!("\"{}\"", value.replace('"', "\"\"").replace('%', "\"^%\"")),
+            Shell::Powershell => format!("'{}'", value.replace('\'', "''")),
+        }
+    }
+
+    pub fn command(&self, command: &str) -> Command {
+        match self {
+            Shell::Sh | Shell::Bash => {
+                let mut process = Command::new(if *self == Shell::Sh { "sh" } else { "bash" });
+                process.arg("-c").arg(command);
+                process
+            }
+            Shell::Cmd => {
+                let mut process = Command::new("cmd.exe");
+                process.args(["/V:OFF", "/C"]); // `!VAR!` is expanded only with delayed expansion
+                raw_arg(&mut process, command);
+                process
+            }
+            Shell::Powershell => {
+                let mut process = Command::new(if cfg!(windows) { "powershell" } else { "pwsh" });
+                process.args(["-NoProfile", "-NonInteractive", "-Command", command]);
+                process
+            }
+        }
+    }
+}
+
+// `cmd.exe` doesn't follow the quoting rules Rust applies to arguments, so the command is passed as is
+#[cfg(windows)]
+fn raw_arg(process: &mut Command, command: &str) {
+    use std::os::windows::process::CommandExt;
+    process.raw_arg(command);
+}
+
+#[cfg(not(windows))]
+fn raw_arg(process: &mut Command, command: &str) {
+    process.arg(command);
+}
@@ -0,0 +1,508 @@
+use std::{fs, path::PathBuf};
+
+use regex::Regex;
+
+use crate::{
+    actions::{ActionError, ActionErrorKind},
+    pipeline_execution_context::PipelineExecutionContext,
+};
+
+// Expression deciding if an action is executed. For example - `ext == "pdf" && size > 10MB`
+//
+// Supports `==`, `!=`, `>`, `>=`, `<`, `<=`, `=~` (regex match), `&&`, `||`, `!` & parentheses.
+// Values are strings (quoted), numbers (optionally suffixed by a size unit - KB, MB, GB, TB), `true`, `false` & `null`.
+// Variables & regex patterns are checked once when parsing. Variables are resolved from the pipeline's execution context:
+//   event - Event type that triggered the pipeline (null on startup)
+//   path, name, stem, ext, size - Of the current file (The last action's file, or the event's file)
+//   error - Failure of the pipeline (null unless in on_failure / finally actions)
+//   previous.exit_code, previous.stdout, previous.stderr - Output of the last command executed
+#[derive(Clone, Debug)]
+pub struct Condition {
+    expression: Expression,
+}
+
+impl Condition {
+    pub fn parse(text: &str) -> Result<Self, ActionError> {
+        let tokens = tokenize(text).map_err(|message| config_error(text, message))?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let expression = parser
+            .parse_or()
+            .map_err(|message| config_error(text, message))?;
+        if parser.position < parser.tokens.len() {
+            return Err(config_error(
+                text,
+                format!("Unexpected {:?}", parser.tokens[parser.position]),
+            ));
+        }
+        Ok(Self { expression })
+    }
+
+    pub fn evaluate(&self, context: &PipelineExecutionContext) -> bool {
+        self.expression.evaluate(context).is_truthy()
+    }
+}
+
+fn config_error<T: AsRef<str>>(text: &str, message: T) -> ActionError {
+    ActionError::new(
+        ActionErrorKind::Config,
+        format!("Invalid condition {:?} - {}", text, message.as_ref()),
+    )
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Identifier(String),
+    Str(String),
+    Number(f64),
+    Operator(&'static str),
+    OpenParen,
+    CloseParen,
+}
+
+const OPERATORS: [&str; 11] = ["==", "!=", ">=", "<=", "=~", "&&", "||", ">", "<", "!", "="];
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' {
+            tokens.push(Token::OpenParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::CloseParen);
+            i += 1;
+        } else if c == '"' || c == '\'' {
+            let mut value = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    Some('\\') if chars.get(i + 1).is_some() => {
+                        value.push(chars[i + 1]);
+                        i += 2;
+                    }
+                    Some(&quote) if quote == c => {
+                        i += 1;
+                        break;
+                    }
+                    Some(&other) => {
+                        value.push(other);
+                        i += 1;
+                    }
+                    None => return Err(String::from("Unterminated string")),
+                }
+            }
+            tokens.push(Token::Str(value));
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let number: f64 = chars[start..i]
+                .iter()
+                .collect::<String>()
+                .parse()
+                .map_err(|_| String::from("Invalid number"))?;
+            let unit_start = i;
+            while i < chars.len() && chars[i].is_ascii_alphabetic() {
+                i += 1;
+            }
+            let unit: String = chars[unit_start..i].iter().collect();
+            tokens.push(Token::Number(number * size_unit_multiplier(&unit)?));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.')
+            {
+                i += 1;
+            }
+            tokens.push(Token::Identifier(chars[start..i].iter().collect()));
+        } else {
+            let rest: String = chars[i..].iter().collect();
+            match OPERATORS
+                .iter()
+                .find(|operator| rest.starts_with(*operator))
+            {
+                Some(&"=") => return Err(String::from("Use `==` for comparisons")),
+                Some(operator) => {
+                    tokens.push(Token::Operator(operator));
+                    i += operator.len();
+                }
+                None => return Err(format!("Unexpected character {:?}", c)),
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn size_unit_multiplier(unit: &str) -> Result<f64, String> {
+    match unit.to_uppercase().as_str() {
+        "" | "B" => Ok(1.0),
+        "KB" => Ok(1024.0),
+        "MB" => Ok(1024.0 * 1024.0),
+        "GB" => Ok(1024.0 * 1024.0 * 1024.0),
+        "TB" => Ok(1024.0 * 1024.0 * 1024.0 * 1024.0),
+        _ => Err(format!("Unknown size unit {:?}", unit)),
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Expression {
+    Literal(Value),
+    Variable(Variable),
+    Not(Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Compare(&'static str, Box<Expression>, Box<Expression>),
+    Match(Box<Expression>, Regex),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Variable {
+    Event,
+    Path,
+    Name,
+    Stem,
+    Ext,
+    Size,
+    Error,
+    PreviousExitCode,
+    PreviousStdout,
+    PreviousStderr,
+}
+
+impl Variable {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "event" => Some(Variable::Event),
+            "path" => Some(Variable::Path),
+            "name" => Some(Variable::Name),
+            "stem" => Some(Variable::Stem),
+            "ext" => Some(Variable::Ext),
+            "size" => Some(Variable::Size),
+            "error" => Some(Variable::Error),
+            "previous.exit_code" => Some(Variable::PreviousExitCode),
+            "previous.stdout" => Some(Variable::PreviousStdout),
+            "previous.stderr" => Some(Variable::PreviousStderr),
+            _ => None,
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn next_operator_is(&self, operators: &[&str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Operator(operator)) if operators.contains(operator) => Some(operator),
+            Some(Token::Identifier(word)) if operators.contains(&word.as_str()) => {
+                // `and`, `or` & `not` keywords
+                OPERATORS
+                    .iter()
+                    .chain(["and", "or", "not"].iter())
+                    .find(|operator| *operator == word)
+                    .copied()
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_and()?;
+        while self.next_operator_is(&["||", "or"]).is_some() {
+            self.position += 1;
+            expression = Expression::Or(Box::new(expression), Box::new(self.parse_and()?));
+        }
+        Ok(expression)
+    }
+
+    fn parse_and(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_not()?;
+        while self.next_operator_is(&["&&", "and"]).is_some() {
+            self.position += 1;
+            expression = Expression::And(Box::new(expression), Box::new(self.parse_not()?));
+        }
+        Ok(expression)
+    }
+
+    fn parse_not(&mut self) -> Result<Expression, String> {
+        if self.next_operator_is(&["!", "not"]).is_some() {
+            self.position += 1;
+            return Ok(Expression::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expression, String> {
+        let left = self.parse_primary()?;
+        match self.next_operator_is(&["==", "!=", ">", ">=", "<", "<=", "=~"]) {
+            Some("=~") => {
+                self.position += 1;
+                match self.next() {
+                    Some(Token::Str(pattern)) => match Regex::new(&pattern) {
+                        Ok(regex) => Ok(Expression::Match(Box::new(left), regex)),
+                        Err(err) => Err(format!("Invalid regex {:?} - {}", pattern, err)),
+                    },
+                    _ => Err(String::from("`=~` requires a quoted regex")),
+                }
+            }
+            Some(operator) => {
+                self.position += 1;
+                let right = self.parse_primary()?;
+                Ok(Expression::Compare(
+                    operator,
+                    Box::new(left),
+                    Box::new(right),
+                ))
+            }
+            None => Ok(left),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, String> {
+        match self.next() {
+            Some(Token::OpenParen) => {
+                let expression = self.parse_or()?;
+                match self.next() {
+                    Some(Token::CloseParen) => Ok(expression),
+                    _ => Err(String::from("Missing closing parenthesis")),
+                }
+            }
+            Some(Token::Str(value)) => Ok(Expression::Literal(Value::Str(value))),
+            Some(Token::Number(value)) => Ok(Expression::Literal(Value::Number(value))),
+            Some(Token::Identifier(name)) => match name.as_str() {
+                "true" => Ok(Expression::Literal(Value::Bool(true))),
+                "false" => Ok(Expression::Literal(Value::Bool(false))),
+                "null" => Ok(Expression::Literal(Value::Null)),
+                _ => Variable::from_name(&name)
+                    .map(Expression::Variable)
+                    .ok_or_else(|| format!("Unknown variable {:?}", name)),
+            },
+            Some(token) => Err(format!("Unexpected {:?}", token)),
+            None => Err(String::from("Unexpected end of expression")),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Str(String),
+    Number(f64),
+    Bool(bool),
+    Null,
+}
+
+impl Value {
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Str(value) => !value.is_empty(),
+            Value::Number(value) => *value != 0.0,
+            Value::Bool(value) => *value,
+            Value::Null => false,
+        }
+    }
+
+    fn from_option<T, F>(value: Option<T>, convert: F) -> Self
+    where
+        F: FnOnce(T) -> Value,
+    {
+        value.map_or(Value::Null, convert)
+    }
+}
+
+impl Expression {
+    fn evaluate(&self, context: &PipelineExecutionContext) -> Value {
+        match self {
+            Expression::Literal(value) => value.clone(),
+            Expression::Variable(variable) => resolve_variable(*variable, context),
+            Expression::Not(expression) => Value::Bool(!expression.evaluate(context).is_truthy()),
+            Expression::And(left, right) => Value::Bool(
+                left.evaluate(context).is_truthy() && right.evaluate(context).is_truthy(),
+            ),
+            Expression::Or(left, right) => Value::Bool(
+                left.evaluate(context).is_truthy() || right.evaluate(context).is_truthy(),
+            ),
+            Expression::Compare(operator, left, right) => Value::Bool(compare(
+                operator,
+                &left.evaluate(context),
+                &right.evaluate(context),
+            )),
+            Expression::Match(expression, regex) => match expression.evaluate(context) {
+                Value::Str(value) => Value::Bool(regex.is_match(&value)),
+                _ => Value::Bool(false),
+            },
+        }
+    }
+}
+
+fn compare(operator: &str, left: &Value, right: &Value) -> bool {
+    let ordering = match (left, right) {
+        (Value::Str(left), Value::Str(right)) => left.partial_cmp(right),
+        (Value::Number(left), Value::Number(right)) => left.partial_cmp(right),
+        (Value::Bool(left), Value::Bool(right)) => left.partial_cmp(right),
+        (Value::Null, Value::Null) => Some(std::cmp::Ordering::Equal),
+        _ => None, // Mismatching types are never equal or ordered
+    };
+    match operator {
+        "==" => ordering == Some(std::cmp::Ordering::Equal),
+        "!=" => ordering != Some(std::cmp::Ordering::Equal),
+        ">" => ordering == Some(std::cmp::Ordering::Greater),
+        ">=" => matches!(
+            ordering,
+            Some(std::cmp::Ordering::Greater) | Some(std::cmp::Ordering::Equal)
+        ),
+        "<" => ordering == Some(std::cmp::Ordering::Less),
+        "<=" => matches!(
+            ordering,
+            Some(std::cmp::Ordering::Less) | Some(std::cmp::Ordering::Equal)
+        ),
+        _ => false,
+    }
+}
+
+fn resolve_variable(variable: Variable, context: &PipelineExecutionContext) -> Value {
+    let file_path: PathBuf = context
+        .action_file_path
+        .clone()
+        .unwrap_or_else(|| context.event_file_path.clone());
+    let os_str_value = |value: Option<&std::ffi::OsStr>| {
+        Value::from_option(value, |value| {
+            Value::Str(value.to_string_lossy().to_string())
+        })
+    };
+    match variable {
+        Variable::Event => Value::from_option(context.event_type.clone(), Value::Str),
+        Variable::Path => Value::Str(file_path.to_string_lossy().to_string()),
+        Variable::Name => os_str_value(file_path.file_name()),
+        Variable::Stem => os_str_value(file_path.file_stem()),
+        Variable::Ext => os_str_value(file_path.extension()),
+        Variable::Size => Value::from_option(fs::metadata(&file_path).ok(), |metadata| {
+            Value::Number(metadata.len() as f64)
+        }),
+        Variable::Error => Value::from_option(context.error.as_ref(), |error| {
+            Value::Str(error.to_string())
+        }),
+        Variable::PreviousExitCode => Value::from_option(
+            context
+                .command_output
+                .as_ref()
+                .and_then(|output| output.exit_code),
+            |exit_code| Value::Number(exit_code as f64),
+        ),
+        Variable::PreviousStdout => Value::from_option(context.command_output.as_ref(), |output| {
+            Value::Str(output.stdout.clone())
+        }),
+        Variable::PreviousStderr => Value::from_option(context.command_output.as_ref(), |output| {
+            Value::Str(output.stderr.clone())
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, sync::Arc};
+
+    use super::*;
+    use crate::{event::HandledEvent, pipeline_config::PipelineConfig};
+
+    fn context() -> PipelineExecutionContext {
+        let event = HandledEvent::new(
+            Some(String::from("create")),
+            PathBuf::from("/nonexistent/folden/report.final.pdf"),
+        );
+        let (trace_tx, _trace_rx) = tokio::sync::broadcast::channel(16);
+        PipelineExecutionContext::new(
+            &event,
+            Path::new("/nonexistent/folden"),
+            Path::new("/nonexistent"),
+            PipelineConfig::default_new(None, None),
+            Arc::new(trace_tx),
+        )
+    }
+
+    fn evaluate(text: &str) -> bool {
+        Condition::parse(text).unwrap().evaluate(&context())
+    }
+
+    fn parse_error(text: &str) -> String {
+        let err = Condition::parse(text).unwrap_err();
+        assert_eq!(err.kind, ActionErrorKind::Config);
+        err.message
+    }
+
+    #[test]
+    fn precedence() {
+        assert!(evaluate("true || false && false"));
+        assert!(!evaluate("(true || false) && false"));
+        assert!(!evaluate("!false && false"));
+        assert!(evaluate("!(false && true)"));
+        assert!(evaluate("not true or true and true"));
+        assert!(evaluate("ext == \"txt\" || ext == \"pdf\" && name != \"\""));
+    }
+
+    #[test]
+    fn quoting() {
+        assert!(evaluate("name == \"report.final.pdf\""));
+        assert!(evaluate("name == 'report.final.pdf'"));
+        assert!(evaluate(r#"'it\'s' == "it's""#));
+        assert!(evaluate(r#""a \"b\"" == 'a "b"'"#));
+        assert!(evaluate("\"a && b\" != ''"));
+    }
+
+    #[test]
+    fn comparisons() {
+        assert!(evaluate("event == \"create\""));
+        assert!(evaluate("stem == \"report.final\" && ext == \"pdf\""));
+        assert!(evaluate("2KB == 2048 && 1.5mb > 1MB && 1GB <= 1024MB"));
+        assert!(evaluate("\"b\" > \"a\" && 3 >= 3 && 2 < 3"));
+        assert!(evaluate(
+            "error == null && size == null && previous.exit_code == null"
+        ));
+        // Mismatching types are never equal or ordered
+        assert!(!evaluate("1 == \"1\""));
+        assert!(evaluate("1 != \"1\""));
+        assert!(!evaluate("null < 1"));
+    }
+
+    #[test]
+    fn regex_matching() {
+        assert!(evaluate("name =~ \"^report.*pdf$\""));
+        assert!(!evaluate("ext =~ \"^(jpg|png)$\""));
+        assert!(evaluate("!(ext =~ 'jpg') && path =~ 'folden'"));
+        // Only strings are matched
+        assert!(!evaluate("error =~ \".*\""));
+    }
+
+    #[test]
+    fn invalid_conditions() {
+        assert!(parse_error("name == \"report").contains("Unterminated string"));
+        assert!(parse_error("ext = \"pdf\"").contains("Use `==`"));
+        assert!(parse_error("extension == \"pdf\"").contains("Unknown variable"));
+        assert!(parse_error("name =~ \"(\"").contains("Invalid regex"));
+        assert!(parse_error("name =~ ext").contains("quoted regex"));
+        assert!(parse_error("size > 1XB").contains("Unknown size unit"));
+        assert!(parse_error("(true || false").contains("Missing closing parenthesis"));
+        assert!(parse_error("true false").contains("Unexpected"));
+        assert!(parse_error("size # 1").contains("Unexpected character"));
+        assert!(parse_error("").contains("Unexpected end of expression"));
+    }
+}
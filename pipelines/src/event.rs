@@ -1,32 +1,73 @@
+use std::{
+    mem,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
 use clap::Values;
 use itertools::Itertools;
-use notify::EventKind;
+use notify::{
+    event::{ModifyKind, RenameMode},
+    EventKind,
+};
 use serde::{Deserialize, Serialize};
 
+use crate::stable_file::StableFileConfig;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PipelineEvent {
     pub events: Vec<String>, // Can flag multiple events in the config to initiate the pipeline against.
     pub naming_regex_match: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>, // Globs matched against the file path relative to the handled directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>, // Globs excluding matched files & any file within matched directories
+    pub min_size: Option<u64>,     // In bytes
+    pub max_size: Option<u64>,     // In bytes
+    pub min_age_secs: Option<u64>, // Time passed since the file was last modified
+    #[serde(default)]
+    pub ignore_hidden: bool, // Ignore hidden files & files within hidden directories
+    pub debounce_ms: Option<u64>, // Quiet window per file path. Bursts of events within it execute the pipeline once.
+    #[serde(default = "PipelineEvent::default_ignore_own_writes_ms")]
+    pub ignore_own_writes_ms: u64, // Events of files written by the pipeline's own actions are ignored for this period
+    pub stable_file: Option<StableFileConfig>, // Wait for files to finish being written to before executing the pipeline
 }
 
-pub const EVENT_TYPES: [&str; 2] = ["create", "modify"];
+pub const EVENT_TYPES: [&str; 5] = ["create", "modify", "remove", "rename", "access"];
+const RENAME_PAIRING_PERIOD: Duration = Duration::from_millis(500);
 
 impl PipelineEvent {
+    fn default_ignore_own_writes_ms() -> u64 {
+        2000
+    }
+
     fn is_handled_event_kind(name: &str, kind: &EventKind) -> bool {
         match name.to_lowercase().as_str() {
             "create" => kind.is_create(),
-            "modify" => kind.is_modify(),
+            // Renames are reported by notify as modifications, but are handled as their own event type
+            "modify" => kind.is_modify() && !matches!(kind, EventKind::Modify(ModifyKind::Name(_))),
+            "remove" => kind.is_remove(),
+            "rename" => matches!(kind, EventKind::Modify(ModifyKind::Name(_))),
+            "access" => kind.is_access(),
             _ => false,
         }
     }
 
+    pub fn handled_event_type(&self, kind: &EventKind) -> Option<&str> {
+        self.events
+            .iter()
+            .find(|event_name| PipelineEvent::is_handled_event_kind(event_name, kind))
+            .map(|event_name| event_name.as_str())
+    }
+
     pub fn is_handled_event(&self, kind: &EventKind) -> bool {
-        for event_name in &self.events {
-            if PipelineEvent::is_handled_event_kind(event_name, kind) {
-                return true;
-            }
-        }
-        false
+        self.handled_event_type(kind).is_some()
+    }
+
+    pub fn is_listed(&self, event_type: &str) -> bool {
+        self.events
+            .iter()
+            .any(|event_name| event_name.eq_ignore_ascii_case(event_type))
     }
 }
 
@@ -35,6 +76,15 @@ impl From<Values<'_>> for PipelineEvent {
         Self {
             events: events.map(|event| event.to_string()).unique().collect(),
             naming_regex_match: Some(String::from(".*")),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            min_size: None,
+            max_size: None,
+            min_age_secs: None,
+            ignore_hidden: false,
+            debounce_ms: None,
+            ignore_own_writes_ms: PipelineEvent::default_ignore_own_writes_ms(),
+            stable_file: None,
         }
     }
 }
@@ -42,8 +92,113 @@ impl From<Values<'_>> for PipelineEvent {
 impl Default for PipelineEvent {
     fn default() -> Self {
         Self {
-            events: EVENT_TYPES.iter().map(|event| event.to_string()).collect(),
+            events: vec![String::from("create"), String::from("modify")],
             naming_regex_match: Some(String::from(".*")),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            min_size: None,
+            max_size: None,
+            min_age_secs: None,
+            ignore_hidden: false,
+            debounce_ms: None,
+            ignore_own_writes_ms: PipelineEvent::default_ignore_own_writes_ms(),
+            stable_file: None,
+        }
+    }
+}
+
+// Watcher event resolved to the file paths the pipeline is applied on
+#[derive(Clone, Debug)]
+pub struct HandledEvent {
+    pub event_type: Option<String>, // Not set when applied on existing files during startup
+    pub file_path: PathBuf,
+    pub renamed_from_file_path: Option<PathBuf>,
+}
+
+impl HandledEvent {
+    pub fn new(event_type: Option<String>, file_path: PathBuf) -> Self {
+        Self {
+            event_type,
+            file_path,
+            renamed_from_file_path: None,
+        }
+    }
+}
+
+// Pairs the separate halves of a rename reported by some backends, into a single rename event.
+// Backends reporting both paths at once (inotify) are used as is.
+// An old path left unpaired for `RENAME_PAIRING_PERIOD` was moved out of the watched directory.
+#[derive(Default)]
+pub struct RenameTracker {
+    pending_from_path: Option<(PathBuf, Instant)>,
+    unpaired_from_paths: Vec<PathBuf>,
+}
+
+impl RenameTracker {
+    // Returns the (from, to) paths once the rename can be resolved
+    pub fn resolve(&mut self, event: &notify::Event) -> Option<(PathBuf, PathBuf)> {
+        match event.kind {
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => match event.paths.as_slice() {
+                [from, to] => {
+                    self.take_pending_from_path();
+                    Some((from.clone(), to.clone()))
+                }
+                _ => None,
+            },
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                if let Some(from) = event.paths.first() {
+                    self.set_pending_from_path(from.clone());
+                }
+                None
+            }
+            // Tracked halves are always followed by a combined event
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) if event.tracker().is_none() => {
+                match (self.take_pending_from_path(), event.paths.first()) {
+                    (Some(from), Some(to)) => Some((from, to.clone())),
+                    _ => None,
+                }
+            }
+            // Backends which can't tell the halves apart (FSEvents) report each path on its own - Only the new one exists
+            EventKind::Modify(ModifyKind::Name(RenameMode::Any)) => match event.paths.as_slice() {
+                [from, to] => {
+                    self.take_pending_from_path();
+                    Some((from.clone(), to.clone()))
+                }
+                [to] if to.exists() => self.take_pending_from_path().map(|from| (from, to.clone())),
+                [from] => {
+                    self.set_pending_from_path(from.clone());
+                    None
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // Old paths of files moved out of the watched directory
+    pub fn take_unpaired(&mut self) -> Vec<PathBuf> {
+        if let Some((_, received_at)) = &self.pending_from_path {
+            if received_at.elapsed() >= RENAME_PAIRING_PERIOD {
+                let (from, _) = self.pending_from_path.take().unwrap();
+                self.unpaired_from_paths.push(from);
+            }
         }
+        mem::take(&mut self.unpaired_from_paths)
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending_from_path
+            .as_ref()
+            .map(|(_, received_at)| *received_at + RENAME_PAIRING_PERIOD)
+    }
+
+    fn set_pending_from_path(&mut self, from: PathBuf) {
+        if let Some((previous_from, _)) = self.pending_from_path.replace((from, Instant::now())) {
+            self.unpaired_from_paths.push(previous_from);
+        }
+    }
+
+    fn take_pending_from_path(&mut self) -> Option<PathBuf> {
+        self.pending_from_path.take().map(|(from, _)| from)
     }
 }
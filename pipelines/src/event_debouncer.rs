@@ -0,0 +1,62 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::event::HandledEvent;
+
+// Coalesces bursts of events per file path.
+// An event is released once no other event arrived for its path within the quiet window.
+pub struct EventDebouncer {
+    window: Duration,
+    pending_events: HashMap<PathBuf, (HandledEvent, Instant)>,
+}
+
+impl EventDebouncer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending_events: HashMap::new(),
+        }
+    }
+
+    // Replaces any pending event of the same path, restarting its quiet window
+    pub fn push(&mut self, event: HandledEvent) {
+        self.push_until(event, Instant::now() + self.window);
+    }
+
+    // Replaces any pending event of the same path, holding it back until the deadline
+    pub fn push_until(&mut self, event: HandledEvent, deadline: Instant) {
+        self.pending_events
+            .insert(event.file_path.clone(), (event, deadline));
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending_events
+            .values()
+            .map(|(_event, deadline)| *deadline)
+            .min()
+    }
+
+    // Removes and returns events whose quiet window has passed, ordered by their deadline
+    pub fn drain_due(&mut self) -> Vec<HandledEvent> {
+        let now = Instant::now();
+        let due_paths: Vec<PathBuf> = self
+            .pending_events
+            .iter()
+            .filter(|(_path, (_event, deadline))| *deadline <= now)
+            .map(|(path, _)| path.clone())
+            .collect();
+        let mut due_events: Vec<(HandledEvent, Instant)> = due_paths
+            .iter()
+            .filter_map(|path| self.pending_events.remove(path))
+            .collect();
+        due_events.sort_by_key(|(_event, deadline)| *deadline);
+        due_events.into_iter().map(|(event, _)| event).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending_events.len()
+    }
+}
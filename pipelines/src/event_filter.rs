@@ -0,0 +1,107 @@
+use std::{
+    fs,
+    path::{Component, Path},
+    time::{Duration, SystemTime},
+};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+use crate::event::PipelineEvent;
+
+#[derive(Debug, PartialEq)]
+pub enum FilterMatch {
+    Matched,
+    Unmatched,
+    TooRecent(Duration), // Matches once the file is left unmodified for the remaining duration
+}
+
+// Compiled file rules of the pipeline event config, applied before any action is executed
+pub struct EventFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    min_age: Option<Duration>,
+    ignore_hidden: bool,
+}
+
+impl EventFilter {
+    pub fn new(event: &PipelineEvent) -> Result<Self, globset::Error> {
+        Ok(Self {
+            include: Self::build_glob_set(&event.include)?,
+            exclude: Self::build_glob_set(&event.exclude)?,
+            min_size: event.min_size,
+            max_size: event.max_size,
+            min_age: event.min_age_secs.map(Duration::from_secs),
+            ignore_hidden: event.ignore_hidden,
+        })
+    }
+
+    fn build_glob_set(patterns: &[String]) -> Result<Option<GlobSet>, globset::Error> {
+        if patterns.is_empty() {
+            return Ok(None);
+        }
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            builder.add(Glob::new(pattern)?);
+        }
+        Ok(Some(builder.build()?))
+    }
+
+    fn requires_metadata(&self) -> bool {
+        self.min_size.is_some() || self.max_size.is_some() || self.min_age.is_some()
+    }
+
+    // Globs are matched against the file path relative to the handler's directory
+    pub fn is_match(&self, directory_path: &Path, file_path: &Path) -> FilterMatch {
+        let relative_path = file_path.strip_prefix(directory_path).unwrap_or(file_path);
+        if self.ignore_hidden && is_hidden(relative_path) {
+            return FilterMatch::Unmatched;
+        }
+        if let Some(include) = &self.include {
+            if !include.is_match(relative_path) {
+                return FilterMatch::Unmatched;
+            }
+        }
+        if let Some(exclude) = &self.exclude {
+            // Excluding a directory excludes everything within it
+            if relative_path
+                .ancestors()
+                .any(|path| !path.as_os_str().is_empty() && exclude.is_match(path))
+            {
+                return FilterMatch::Unmatched;
+            }
+        }
+        if self.requires_metadata() {
+            // Files that can't be inspected (removed, inaccessible) don't match size / age rules
+            let metadata = match fs::metadata(file_path) {
+                Ok(metadata) => metadata,
+                Err(_) => return FilterMatch::Unmatched,
+            };
+            let len = metadata.len();
+            if matches!(self.min_size, Some(min_size) if len < min_size)
+                || matches!(self.max_size, Some(max_size) if len > max_size)
+            {
+                return FilterMatch::Unmatched;
+            }
+            if let Some(min_age) = self.min_age {
+                let age = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                    .unwrap_or_default();
+                if age < min_age {
+                    return FilterMatch::TooRecent(min_age - age);
+                }
+            }
+        }
+        FilterMatch::Matched
+    }
+}
+
+fn is_hidden(relative_path: &Path) -> bool {
+    relative_path.components().any(|component| match component {
+        Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+        _ => false,
+    })
+}
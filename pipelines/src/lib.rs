@@ -1,6 +1,10 @@
 pub mod actions;
+mod condition;
 pub mod event;
+mod event_debouncer;
+mod event_filter;
 pub mod pipeline_config;
 mod pipeline_context_input;
 mod pipeline_execution_context;
 pub mod pipeline_handler;
+mod stable_file;
@@ -1,17 +1,34 @@
-use std::{convert::TryFrom, fs, io, path::Path};
+use std::{convert::TryFrom, fs, io, path::Path, time::Duration};
 
 use clap::Values;
 use serde::{Deserialize, Serialize};
 
 use crate::{actions::PipelineActions, event::PipelineEvent};
 
+const DEFAULT_POLL_INTERVAL_MS: u64 = 5000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatcherKind {
+    #[default]
+    Native, // Platform's file system notification API
+    Poll, // Periodic directory scans. Required for network & FUSE file systems (NFS, SMB, sshfs)
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PipelineConfig {
     pub watch_recursive: bool,
     pub apply_on_startup_on_existing_files: bool,
     pub panic_handler_on_error: bool,
+    #[serde(default)]
+    pub watcher: WatcherKind,
+    pub poll_interval_ms: Option<u64>, // Only used by the poll watcher
     pub event: PipelineEvent,
     pub actions: Vec<PipelineActions>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_failure: Vec<PipelineActions>, // Executed once any of the actions failed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub finally: Vec<PipelineActions>, // Executed at the end of every pipeline execution, failed or not
 }
 
 impl PipelineConfig {
@@ -20,6 +37,8 @@ impl PipelineConfig {
             watch_recursive: false,
             apply_on_startup_on_existing_files: false,
             panic_handler_on_error: false,
+            watcher: WatcherKind::default(),
+            poll_interval_ms: None,
             event: match events {
                 Some(events) => PipelineEvent::from(events),
                 None => PipelineEvent::default(),
@@ -28,9 +47,15 @@ impl PipelineConfig {
                 Some(actions) => PipelineActions::defaults(actions),
                 None => vec![PipelineActions::default()],
             },
+            on_failure: Vec::new(),
+            finally: Vec::new(),
         }
     }
 
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_ms.unwrap_or(DEFAULT_POLL_INTERVAL_MS))
+    }
+
     pub fn generate_config(&self, path: &Path) -> io::Result<()> {
         fs::write(path, toml::to_vec(*Box::new(self)).unwrap())
     }
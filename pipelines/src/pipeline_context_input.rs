@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum PipelineContextInput {
     EventFilePath,
     ActionFilePath,
+    RenamedFromFilePath,
 }
@@ -1,40 +1,82 @@
 use std::{
+    collections::HashMap,
     path::{Path, PathBuf},
     sync::Arc,
 };
 
-use crate::{pipeline_config::PipelineConfig, pipeline_context_input::PipelineContextInput};
+use regex::Regex;
+
+use crate::{
+    actions::{ActionError, CommandOutput, DeduplicationIndex},
+    event::HandledEvent,
+    pipeline_config::PipelineConfig,
+    pipeline_context_input::PipelineContextInput,
+};
 use generated_types::TraceHandlerResponse;
 
 type OutputTraceSender =
     Arc<tokio::sync::broadcast::Sender<Result<TraceHandlerResponse, tonic::Status>>>;
+type FinishedCallback = Box<dyn FnOnce(&mut PipelineExecutionContext)>;
 
 pub struct PipelineExecutionContext {
     pub config: PipelineConfig,
+    pub handler_directory_path: PathBuf, // Directory the handler is watching
+    pub state_dir_path: PathBuf,         // Directory of state kept by actions across executions
+    pub event_type: Option<String>,
     pub event_file_path: PathBuf,
+    pub renamed_from_file_path: Option<PathBuf>,
     pub action_file_path: Option<PathBuf>,
     pub trace_tx: OutputTraceSender,
     pub action_name: Option<String>,
+    pub written_file_paths: Vec<PathBuf>, // Files created / removed by actions, which the handler should not react to
+    pub command_output: Option<CommandOutput>, // Output of the last command executed by the pipeline
+    pub error: Option<ActionError>, // Failure of the pipeline's actions. Set while executing on_failure & finally actions
+    pub variables: HashMap<String, String>, // Values set by actions for following actions' placeholders (`$sha256$`)
+    pub naming_regex: Option<Regex>,        // The handler's compiled `naming_regex_match`
+    pub deduplication_index: Option<DeduplicationIndex>, // The handler's index, lent for the execution
+    pub on_finished: Vec<FinishedCallback>, // Called in order once the pipeline's execution ends, with its final state
 }
 
-impl<'a> PipelineExecutionContext {
-    pub fn new<T>(event_file_path: T, config: PipelineConfig, trace_tx: OutputTraceSender) -> Self
-    where
-        T: AsRef<Path>,
-    {
+impl PipelineExecutionContext {
+    pub fn new(
+        event: &HandledEvent,
+        handler_directory_path: &Path,
+        state_dir_path: &Path,
+        config: PipelineConfig,
+        trace_tx: OutputTraceSender,
+    ) -> Self {
         Self {
             config,
-            event_file_path: event_file_path.as_ref().to_path_buf(),
+            handler_directory_path: handler_directory_path.to_path_buf(),
+            state_dir_path: state_dir_path.to_path_buf(),
+            event_type: event.event_type.clone(),
+            event_file_path: event.file_path.clone(),
+            renamed_from_file_path: event.renamed_from_file_path.clone(),
             action_file_path: None,
             trace_tx,
             action_name: None,
+            written_file_paths: Vec::new(),
+            command_output: None,
+            error: None,
+            variables: HashMap::new(),
+            naming_regex: None,
+            deduplication_index: None,
+            on_finished: Vec::new(),
         }
     }
 
+    pub fn mark_written<T>(&mut self, path: T)
+    where
+        T: AsRef<Path>,
+    {
+        self.written_file_paths.push(path.as_ref().to_path_buf());
+    }
+
     pub fn get_input(&self, input: PipelineContextInput) -> Option<PathBuf> {
         match input {
             PipelineContextInput::EventFilePath => Some(self.event_file_path.clone()),
             PipelineContextInput::ActionFilePath => self.action_file_path.clone(),
+            PipelineContextInput::RenamedFromFilePath => self.renamed_from_file_path.clone(),
         }
     }
 
@@ -46,16 +88,12 @@ impl<'a> PipelineExecutionContext {
         self.send_trace_message(msg);
     }
 
-    pub fn handle_error<T>(&self, msg: T) -> bool
+    pub fn log_error<T>(&self, msg: T)
     where
         T: AsRef<str>,
     {
         tracing::error!("{}", msg.as_ref());
         self.send_trace_message(msg.as_ref());
-        if self.config.panic_handler_on_error {
-            panic!("{}", msg.as_ref());
-        }
-        false
     }
 
     fn send_trace_message<T>(&self, msg: T)
@@ -1,111 +1,516 @@
+use std::collections::{HashMap, VecDeque};
 use std::fs;
-use std::path::Path;
+use std::mem;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use crossbeam::channel::Receiver;
-use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use crossbeam::channel::{Receiver, RecvTimeoutError};
+use notify::event::{AccessKind, AccessMode, ModifyKind};
+use notify::{EventKind, RecursiveMode, Watcher};
 use regex::Regex;
 
-use crate::actions::PipelineAction;
+use crate::actions::{
+    ActionError, ActionErrorKind, ActionOutcome, ActionResult, DeduplicationIndex, PipelineAction,
+    PipelineActions,
+};
+use crate::condition::Condition;
+use crate::event::{HandledEvent, RenameTracker};
+use crate::event_debouncer::EventDebouncer;
+use crate::event_filter::{EventFilter, FilterMatch};
 use crate::pipeline_config::PipelineConfig;
 use crate::pipeline_execution_context::PipelineExecutionContext;
+use crate::stable_file::FileSnapshot;
 use generated_types::TraceHandlerResponse;
 
 type OutputTraceSender =
     Arc<tokio::sync::broadcast::Sender<Result<TraceHandlerResponse, tonic::Status>>>;
+type WatcherEventsReceiver = Receiver<Result<notify::Event, notify::Error>>;
 
 pub struct PipelineHandler {
+    pub directory_path: PathBuf,
+    pub state_dir_path: PathBuf, // Directory of state kept by actions across executions
+    deduplication_index: Option<DeduplicationIndex>, // Loaded by the first Deduplicate action executed
     pub config: PipelineConfig,
     pub naming_regex: Option<Regex>,
     pub trace_tx: OutputTraceSender,
+    event_filter: EventFilter,
+    recent_events: EventDebouncer, // Events of files younger than `min_age_secs`, handled again once old enough
+    deferred_events: VecDeque<Result<notify::Event, notify::Error>>, // Events read while waiting on a file, yet to be processed
+    own_written_files: HashMap<PathBuf, Instant>, // Files written by the pipeline's actions, mapped to until when their events are ignored
+    conditions: PipelineConditions,
+}
+
+// Parsed `when` conditions, by the index of their action
+struct PipelineConditions {
+    actions: Vec<Option<Condition>>,
+    on_failure: Vec<Option<Condition>>,
+    finally: Vec<Option<Condition>>,
 }
 
 impl PipelineHandler {
-    pub fn new(config: PipelineConfig, trace_tx: OutputTraceSender) -> Self {
+    // Fails if the config is invalid, so the handler isn't started
+    pub fn new(
+        directory_path: PathBuf,
+        state_dir_path: PathBuf,
+        config: PipelineConfig,
+        trace_tx: OutputTraceSender,
+    ) -> Result<Self, ActionError> {
         let mut naming_regex: Option<Regex> = None;
         if let Some(naming_regex_match) = config.event.naming_regex_match.to_owned() {
-            naming_regex = Some(Regex::new(&naming_regex_match).unwrap());
+            naming_regex = Some(Regex::new(&naming_regex_match).map_err(|err| {
+                ActionError::new(
+                    ActionErrorKind::Config,
+                    format!("Invalid naming_regex_match - {}", err),
+                )
+            })?);
         }
-        Self {
+        let event_filter = EventFilter::new(&config.event).map_err(|err| {
+            ActionError::new(
+                ActionErrorKind::Config,
+                format!("Invalid event filter - {}", err),
+            )
+        })?;
+        let conditions = PipelineConditions {
+            actions: Self::prepare_actions(&config.actions)?,
+            on_failure: Self::prepare_actions(&config.on_failure)?,
+            finally: Self::prepare_actions(&config.finally)?,
+        };
+        Ok(Self {
+            directory_path,
+            state_dir_path,
+            deduplication_index: None,
             config,
             naming_regex,
             trace_tx,
-        }
+            event_filter,
+            recent_events: EventDebouncer::new(Duration::ZERO),
+            deferred_events: VecDeque::new(),
+            own_written_files: HashMap::new(),
+            conditions,
+        })
+    }
+
+    // Validates the actions' settings, and parses their `when` conditions
+    fn prepare_actions(actions: &[PipelineActions]) -> Result<Vec<Option<Condition>>, ActionError> {
+        actions
+            .iter()
+            .map(|action| {
+                let action_name: &'static str = action.into();
+                let action_error = |err: ActionError| {
+                    ActionError::new(err.kind, format!("{} - {}", action_name, err.message))
+                };
+                action.validate().map_err(action_error)?;
+                action
+                    .when()
+                    .map(Condition::parse)
+                    .transpose()
+                    .map_err(action_error)
+            })
+            .collect()
     }
 
-    fn handle(&self, file_path: &Path) {
+    fn handle(&mut self, event: &HandledEvent, watcher_rx: &WatcherEventsReceiver) {
         if let Some(naming_regex) = &self.naming_regex {
-            if !naming_regex.is_match(file_path.to_str().unwrap()) {
+            if !naming_regex.is_match(event.file_path.to_str().unwrap()) {
+                return;
+            }
+        }
+        match self
+            .event_filter
+            .is_match(&self.directory_path, &event.file_path)
+        {
+            FilterMatch::Matched => {}
+            FilterMatch::Unmatched => {
+                tracing::debug!("Event filtered out - {:?}", &event.file_path);
+                return;
+            }
+            FilterMatch::TooRecent(remaining) => {
+                tracing::debug!("Deferring event of recent file - {:?}", &event.file_path);
+                self.recent_events
+                    .push_until(event.clone(), Instant::now() + remaining);
                 return;
             }
         }
-        self.execute_pipeline(file_path);
+        if let Some(stable_file) = &self.config.event.stable_file {
+            if stable_file.is_partial_file(&event.file_path) {
+                tracing::debug!("Skipping partial file - {:?}", &event.file_path);
+                return;
+            }
+            let is_removed = event.event_type.as_deref() == Some("remove");
+            if !is_removed && !self.wait_for_stable_file(&event.file_path, watcher_rx) {
+                return;
+            }
+        }
+        self.execute_pipeline(event);
     }
 
-    fn execute_pipeline(&self, file_path: &Path) {
-        let mut context =
-            PipelineExecutionContext::new(file_path, self.config.clone(), self.trace_tx.clone());
-        for action in &self.config.actions {
+    // Blocks until the file's size & modification time stop changing, or a close-after-write event is received for it.
+    // Other events read meanwhile are deferred, while further write events of the file are absorbed.
+    fn wait_for_stable_file(
+        &mut self,
+        file_path: &Path,
+        watcher_rx: &WatcherEventsReceiver,
+    ) -> bool {
+        let stable_file = self.config.event.stable_file.clone().unwrap();
+        let started_at = Instant::now();
+        let mut snapshot = match FileSnapshot::take(file_path) {
+            Some(snapshot) => snapshot,
+            None => {
+                tracing::debug!("File no longer exists - {:?}", file_path);
+                return false;
+            }
+        };
+        // Files last modified before the quiet period (Existing files, completed downloads) aren't waited on
+        let mut stable_since = started_at
+            .checked_sub(snapshot.unchanged_for().min(stable_file.quiet_period()))
+            .unwrap_or(started_at);
+        loop {
+            let now = Instant::now();
+            if now.duration_since(stable_since) >= stable_file.quiet_period() {
+                return true;
+            }
+            if now.duration_since(started_at) >= stable_file.timeout() {
+                tracing::warn!("File didn't finish being written to - {:?}", file_path);
+                return false;
+            }
+            let next_check =
+                (stable_since + stable_file.quiet_period()).min(now + stable_file.poll_interval());
+            match watcher_rx.recv_deadline(next_check) {
+                Ok(Ok(event)) if event.paths.len() == 1 && event.paths[0] == file_path => {
+                    match event.kind {
+                        EventKind::Access(AccessKind::Close(AccessMode::Write)) => return true,
+                        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Access(_) => {}
+                        _ => self.deferred_events.push_back(Ok(event)),
+                    }
+                }
+                Ok(Err(error)) => {
+                    let is_stopped = matches!(error.kind, notify::ErrorKind::WatchNotFound);
+                    self.deferred_events.push_back(Err(error));
+                    if is_stopped {
+                        return false;
+                    }
+                }
+                Ok(result) => self.deferred_events.push_back(result),
+                Err(RecvTimeoutError::Timeout) => match FileSnapshot::take(file_path) {
+                    Some(current_snapshot) => {
+                        if current_snapshot != snapshot {
+                            snapshot = current_snapshot;
+                            stable_since = Instant::now();
+                        }
+                    }
+                    None => {
+                        tracing::debug!("File no longer exists - {:?}", file_path);
+                        return false;
+                    }
+                },
+                Err(RecvTimeoutError::Disconnected) => return false,
+            }
+        }
+    }
+
+    fn execute_pipeline(&mut self, event: &HandledEvent) {
+        let mut context = PipelineExecutionContext::new(
+            event,
+            &self.directory_path,
+            &self.state_dir_path,
+            self.config.clone(),
+            self.trace_tx.clone(),
+        );
+        context.naming_regex = self.naming_regex.clone();
+        context.deduplication_index = self.deduplication_index.take();
+        let mut errors = Vec::new();
+        if let Err(err) =
+            Self::run_actions(&self.config.actions, &self.conditions.actions, &mut context)
+        {
+            context.error = Some(err.clone());
+            errors.push(err);
+            if !self.config.on_failure.is_empty() {
+                context.action_name = None;
+                context.log("Executing on_failure actions");
+                if let Err(err) = Self::run_actions(
+                    &self.config.on_failure,
+                    &self.conditions.on_failure,
+                    &mut context,
+                ) {
+                    errors.push(err);
+                }
+            }
+        }
+        if !self.config.finally.is_empty() {
+            context.action_name = None;
+            context.log("Executing finally actions");
+            if let Err(err) =
+                Self::run_actions(&self.config.finally, &self.conditions.finally, &mut context)
+            {
+                errors.push(err);
+            }
+        }
+        for on_finished in mem::take(&mut context.on_finished) {
+            on_finished(&mut context);
+        }
+        self.deduplication_index = context.deduplication_index.take();
+        let ignored_until =
+            Instant::now() + Duration::from_millis(self.config.event.ignore_own_writes_ms);
+        for file_path in context.written_file_paths {
+            self.own_written_files.insert(file_path, ignored_until);
+        }
+        if let Some(err) = errors.first() {
+            if self.config.panic_handler_on_error {
+                panic!("{}", err);
+            }
+        }
+    }
+
+    // Runs actions sequentially, stopping at the first failing or halting action
+    fn run_actions(
+        actions: &[PipelineActions],
+        conditions: &[Option<Condition>],
+        context: &mut PipelineExecutionContext,
+    ) -> Result<(), ActionError> {
+        for (action, condition) in actions.iter().zip(conditions) {
             let action_name: &'static str = action.into();
             context.action_name = Some(action_name.into());
             context.log("Starting action");
-            let action_succeeded = action.run(&mut context);
-            if !action_succeeded {
-                break;
+            let result = match condition {
+                Some(condition) if !condition.evaluate(context) => Ok(ActionOutcome::Skipped(
+                    format!("Condition not met - {}", action.when().unwrap_or_default()),
+                )),
+                _ => Self::run_action(action, context),
+            };
+            match result {
+                Ok(ActionOutcome::Completed) => {}
+                Ok(ActionOutcome::Skipped(reason)) => {
+                    context.log(format!("Action skipped - {}", reason));
+                }
+                Ok(ActionOutcome::Halted(reason)) => {
+                    context.log(format!("Pipeline halted - {}", reason));
+                    break;
+                }
+                Err(err) => {
+                    context.log_error(format!("Action failed - {}", err));
+                    return Err(err);
+                }
             }
         }
+        Ok(())
+    }
+
+    // Runs the action, retrying retryable failures according to the action's retry policy
+    fn run_action(
+        action: &PipelineActions,
+        context: &mut PipelineExecutionContext,
+    ) -> ActionResult {
+        let retry_policy = match action.retry_policy() {
+            Some(retry_policy) => retry_policy,
+            None => return action.run(context),
+        };
+        let attempts = retry_policy.attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            match action.run(context) {
+                Err(err) if err.is_retryable() && attempt < attempts => {
+                    let delay = retry_policy.delay(attempt);
+                    context.log(format!(
+                        "Attempt {}/{} failed - {}. Retrying in {}ms",
+                        attempt,
+                        attempts,
+                        err,
+                        delay.as_millis()
+                    ));
+                    thread::sleep(delay);
+                    attempt += 1;
+                    context.log(format!("Starting attempt {}/{}", attempt, attempts));
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn is_own_written_file(&mut self, file_path: &Path) -> bool {
+        let now = Instant::now();
+        self.own_written_files
+            .retain(|_path, ignored_until| *ignored_until > now);
+        self.own_written_files.contains_key(file_path)
     }
 
-    fn apply_on_existing_files(&self, path: &Path) {
-        for entry in fs::read_dir(path).unwrap() {
+    fn apply_on_existing_files(&mut self, watcher_rx: &WatcherEventsReceiver) {
+        for entry in fs::read_dir(&self.directory_path).unwrap() {
             let entry = entry.unwrap();
             let metadata = entry.metadata().unwrap();
             if metadata.is_file() {
-                self.handle(&entry.path());
+                self.handle(&HandledEvent::new(None, entry.path()), watcher_rx);
+            }
+        }
+    }
+
+    fn resolve_event(
+        &self,
+        event: &notify::Event,
+        rename_tracker: &mut RenameTracker,
+    ) -> Option<HandledEvent> {
+        // Renames are tracked regardless of the handled events, to tell files moved out of the directory apart
+        if let EventKind::Modify(ModifyKind::Name(_)) = event.kind {
+            let (from_path, to_path) = rename_tracker.resolve(event)?;
+            if self.config.event.is_listed("rename") {
+                let mut handled_event = HandledEvent::new(Some(String::from("rename")), to_path);
+                handled_event.renamed_from_file_path = Some(from_path);
+                Some(handled_event)
+            } else if self.is_completed_partial_file(&from_path, &to_path) {
+                // Downloads are completed by renaming their partial file - Handled as the file's creation
+                let event_type = ["create", "modify"]
+                    .iter()
+                    .find(|event_type| self.config.event.is_listed(event_type))?;
+                Some(HandledEvent::new(Some(event_type.to_string()), to_path))
+            } else {
+                None
+            }
+        } else {
+            let event_type = self.config.event.handled_event_type(&event.kind)?;
+            let event_file_path = event.paths.first()?;
+            // The poll watcher reports changes of the watched directory itself
+            if *event_file_path == self.directory_path {
+                return None;
+            }
+            Some(HandledEvent::new(
+                Some(event_type.to_lowercase()),
+                event_file_path.to_path_buf(),
+            ))
+        }
+    }
+
+    fn is_completed_partial_file(&self, from_path: &Path, to_path: &Path) -> bool {
+        match &self.config.event.stable_file {
+            Some(stable_file) => {
+                stable_file.is_partial_file(from_path) && !stable_file.is_partial_file(to_path)
             }
+            None => false,
         }
     }
 
-    fn on_watch(&self, watcher_rx: Receiver<Result<notify::Event, notify::Error>>) {
-        for result in watcher_rx {
+    fn next_watcher_event(
+        &mut self,
+        watcher_rx: &WatcherEventsReceiver,
+        deadline: Option<Instant>,
+    ) -> Result<Option<Result<notify::Event, notify::Error>>, RecvTimeoutError> {
+        if let Some(result) = self.deferred_events.pop_front() {
+            return Ok(Some(result));
+        }
+        match deadline {
+            Some(deadline) => match watcher_rx.recv_deadline(deadline) {
+                Ok(result) => Ok(Some(result)),
+                Err(RecvTimeoutError::Timeout) => Ok(None),
+                Err(err) => Err(err),
+            },
+            None => match watcher_rx.recv() {
+                Ok(result) => Ok(Some(result)),
+                Err(_) => Err(RecvTimeoutError::Disconnected),
+            },
+        }
+    }
+
+    fn dispatch(
+        &mut self,
+        handled_event: HandledEvent,
+        debouncer: &mut Option<EventDebouncer>,
+        watcher_rx: &WatcherEventsReceiver,
+    ) {
+        if self.is_own_written_file(&handled_event.file_path) {
+            tracing::debug!(
+                "Ignoring event of file written by pipeline - {:?}",
+                &handled_event.file_path
+            );
+            return;
+        }
+        match debouncer.as_mut() {
+            Some(debouncer) => debouncer.push(handled_event),
+            None => self.handle(&handled_event, watcher_rx),
+        }
+    }
+
+    fn on_watch(&mut self, watcher_rx: &WatcherEventsReceiver) {
+        let mut rename_tracker = RenameTracker::default();
+        let mut debouncer = self
+            .config
+            .event
+            .debounce_ms
+            .map(|debounce_ms| EventDebouncer::new(Duration::from_millis(debounce_ms)));
+        loop {
+            let deadline = debouncer
+                .as_ref()
+                .and_then(|debouncer| debouncer.next_deadline())
+                .into_iter()
+                .chain(rename_tracker.next_deadline())
+                .chain(self.recent_events.next_deadline())
+                .min();
+            let result = match self.next_watcher_event(watcher_rx, deadline) {
+                Ok(result) => result,
+                Err(_) => break,
+            };
             match result {
-                Ok(event) => {
-                    if self.config.event.is_handled_event(&event.kind) {
+                Some(Ok(event)) => {
+                    if let Some(handled_event) = self.resolve_event(&event, &mut rename_tracker) {
                         tracing::debug!("Event to handle - {:?}", &event.kind);
-                        let event_file_path = event.paths.first().unwrap();
-                        self.handle(event_file_path);
+                        self.dispatch(handled_event, &mut debouncer, watcher_rx);
                     }
                 }
-                Err(error) => {
+                Some(Err(error)) => {
                     tracing::warn!("Watcher error - {:?}", error);
                     if let notify::ErrorKind::WatchNotFound = error.kind {
                         break;
                     }
                 }
+                None => {}
+            }
+            // Files moved out of the directory are handled as removed
+            for from_path in rename_tracker.take_unpaired() {
+                if self.config.event.is_listed("remove") {
+                    tracing::debug!("Event to handle - Moved out {:?}", &from_path);
+                    let handled_event = HandledEvent::new(Some(String::from("remove")), from_path);
+                    self.dispatch(handled_event, &mut debouncer, watcher_rx);
+                }
+            }
+            if let Some(debouncer) = debouncer.as_mut() {
+                for handled_event in debouncer.drain_due() {
+                    self.handle(&handled_event, watcher_rx);
+                }
+            }
+            for handled_event in self.recent_events.drain_due() {
+                self.handle(&handled_event, watcher_rx);
+            }
+        }
+        if self.recent_events.len() > 0 {
+            tracing::info!(
+                "Dropped {} pending events of recent files",
+                self.recent_events.len()
+            );
+        }
+        if let Some(debouncer) = debouncer {
+            if debouncer.len() > 0 {
+                tracing::info!("Dropped {} pending debounced events", debouncer.len());
             }
         }
     }
 
-    pub fn watch(
-        &mut self,
-        path: &Path,
-        mut watcher: RecommendedWatcher,
-        events_rx: Receiver<Result<notify::Event, notify::Error>>,
-    ) {
+    pub fn watch<W>(&mut self, mut watcher: W, events_rx: WatcherEventsReceiver)
+    where
+        W: Watcher,
+    {
         let recursive_mode = if self.config.watch_recursive {
             RecursiveMode::Recursive
         } else {
             RecursiveMode::NonRecursive
         };
-        watcher.watch(&*path, recursive_mode).unwrap();
+        watcher.watch(&self.directory_path, recursive_mode).unwrap();
         if self.config.apply_on_startup_on_existing_files {
-            self.apply_on_existing_files(path);
+            self.apply_on_existing_files(&events_rx);
             tracing::info!("Ended startup phase");
         }
-        self.on_watch(events_rx);
+        self.on_watch(&events_rx);
         tracing::info!("Ending watch");
         let _ = self.trace_tx.send(Ok(TraceHandlerResponse {
-            directory_path: path.to_str().unwrap().to_string(),
+            directory_path: self.directory_path.to_str().unwrap().to_string(),
             action: None,
             message: "Handler runtime ended".to_string(),
         }));
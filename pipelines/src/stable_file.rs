@@ -0,0 +1,85 @@
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+
+// Holds back pipeline execution until the event's file stopped being written to
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StableFileConfig {
+    #[serde(default = "StableFileConfig::default_quiet_period_ms")]
+    pub quiet_period_ms: u64, // Size & modification time are required to stay unchanged for this period
+    #[serde(default = "StableFileConfig::default_timeout_ms")]
+    pub timeout_ms: u64, // Event is dropped if the file didn't settle by then
+    #[serde(default = "StableFileConfig::default_partial_suffixes")]
+    pub partial_suffixes: Vec<String>, // Files still being downloaded are never handled
+}
+
+impl StableFileConfig {
+    fn default_quiet_period_ms() -> u64 {
+        1000
+    }
+
+    fn default_timeout_ms() -> u64 {
+        60000
+    }
+
+    fn default_partial_suffixes() -> Vec<String> {
+        vec![
+            String::from(".part"),
+            String::from(".crdownload"),
+            String::from(".tmp"),
+        ]
+    }
+
+    pub fn quiet_period(&self) -> Duration {
+        Duration::from_millis(self.quiet_period_ms)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+
+    // Interval between file state checks. Kept short relative to the quiet period
+    pub fn poll_interval(&self) -> Duration {
+        (self.quiet_period() / 4).clamp(Duration::from_millis(10), Duration::from_millis(250))
+    }
+
+    pub fn is_partial_file(&self, path: &Path) -> bool {
+        match path.file_name() {
+            Some(file_name) => {
+                let file_name = file_name.to_string_lossy().to_lowercase();
+                self.partial_suffixes
+                    .iter()
+                    .any(|suffix| file_name.ends_with(&suffix.to_lowercase()))
+            }
+            None => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileSnapshot {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileSnapshot {
+    // Returns None if the file can't be read (removed / renamed away)
+    pub fn take(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+
+    // Time since the file was last modified. Zero if unknown
+    pub fn unchanged_for(&self) -> Duration {
+        self.modified
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .unwrap_or_default()
+    }
+}
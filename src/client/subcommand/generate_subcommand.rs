@@ -43,7 +43,7 @@ impl SubCommandUtil for GenerateSubCommand {
         false
     }
 
-    fn construct_subcommand(&self) -> App {
+    fn construct_subcommand(&self) -> App<'_, '_> {
         self.create_instance()
             .about("Generate default handler pipeline config")
             .arg(
@@ -62,7 +62,7 @@ impl SubCommandUtil for GenerateSubCommand {
                     .multiple(true)
                     .empty_values(false)
                     .case_insensitive(true)
-                    .possible_values(&PipelineActions::VARIANTS),
+                    .possible_values(PipelineActions::VARIANTS),
             )
             .arg(
                 Arg::with_name("path")
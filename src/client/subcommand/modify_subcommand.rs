@@ -24,7 +24,7 @@ impl SubCommandUtil for ModifySubCommand {
         true
     }
 
-    fn construct_subcommand(&self) -> App {
+    fn construct_subcommand(&self) -> App<'_, '_> {
         self.create_instance()
             .about("Modify directory handler")
             .arg(
@@ -27,7 +27,7 @@ impl SubCommandUtil for RegisterSubCommand {
         true
     }
 
-    fn construct_subcommand(&self) -> App {
+    fn construct_subcommand(&self) -> App<'_, '_> {
         self.create_instance()
             .about("Register handler pipeline to directory")
             .arg(
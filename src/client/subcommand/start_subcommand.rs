@@ -26,7 +26,7 @@ impl SubCommandUtil for StartSubCommand {
         true
     }
 
-    fn construct_subcommand(&self) -> App {
+    fn construct_subcommand(&self) -> App<'_, '_> {
         self.create_instance()
             .about("Start handler on directory")
             .args(construct_directory_or_all_args().as_slice())
@@ -24,7 +24,7 @@ impl SubCommandUtil for StatusSubCommand {
         true
     }
 
-    fn construct_subcommand(&self) -> App {
+    fn construct_subcommand(&self) -> App<'_, '_> {
         self.create_instance()
             .about("Status of a registered handler given a directory")
             .args(construct_directory_or_all_args().as_slice())
@@ -26,7 +26,7 @@ impl SubCommandUtil for StopSubCommand {
         true
     }
 
-    fn construct_subcommand(&self) -> App {
+    fn construct_subcommand(&self) -> App<'_, '_> {
         self.create_instance()
             .about("Stop handler on directory")
             .arg(
@@ -23,7 +23,7 @@ pub trait SubCommandUtil: DynClone {
 
     fn requires_connection(&self) -> bool;
 
-    fn construct_subcommand(&self) -> App;
+    fn construct_subcommand(&self) -> App<'_, '_>;
 
     fn subcommand_runtime(&self, _sub_matches: &ArgMatches) {
         panic!("Command execution without connection unsupported")
@@ -35,7 +35,7 @@ pub trait SubCommandUtil: DynClone {
         client: HandlerServiceClient<tonic::transport::Channel>,
     );
 
-    fn create_instance(&self) -> App {
+    fn create_instance(&self) -> App<'_, '_> {
         if self.alias().is_empty() {
             SubCommand::with_name(self.name())
         } else {
@@ -62,7 +62,7 @@ impl SubCommandCollection {
         self.0.push(elem);
     }
 
-    pub fn collect_as_apps(&self) -> Vec<App> {
+    pub fn collect_as_apps(&self) -> Vec<App<'_, '_>> {
         self.0
             .as_slice()
             .iter()
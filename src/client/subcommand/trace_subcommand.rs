@@ -25,7 +25,7 @@ impl SubCommandUtil for TraceSubCommand {
         true
     }
 
-    fn construct_subcommand(&self) -> App {
+    fn construct_subcommand(&self) -> App<'_, '_> {
         self.create_instance()
             .about("Trace directory handler output")
             .arg(construct_port_arg())
@@ -20,6 +20,14 @@ pub struct Config {
 }
 
 impl Config {
+    // Directory of handlers' state kept by actions (Deduplication indexes), next to the mapping state
+    pub fn state_dir_path(&self) -> PathBuf {
+        self.mapping_state_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default()
+    }
+
     pub fn save(&self, file_path: &Path) -> Result<(), std::io::Error> {
         let config_data: Vec<u8> = self.into();
         fs::write(file_path, config_data)
@@ -4,17 +4,21 @@ use std::{
     fs,
     path::{Path, PathBuf},
     result::Result,
-    sync::Arc,
+    sync::{Arc, Mutex},
     thread,
 };
 
-use notify::{RecommendedWatcher, Watcher};
+use crossbeam::channel::Receiver;
+use notify::{PollWatcher, RecommendedWatcher, Watcher};
 use serde::{Deserialize, Serialize};
 use tokio::sync::broadcast;
 
 use crate::{config::Config, handler_mapping::HandlerMapping};
 use generated_types::HandlerStateResponse;
-use pipelines::{pipeline_config::PipelineConfig, pipeline_handler::PipelineHandler};
+use pipelines::{
+    pipeline_config::{PipelineConfig, WatcherKind},
+    pipeline_handler::PipelineHandler,
+};
 
 // Mapping data used to handle known directories to handle
 // If a handler thread has ceased isn't known at realtime rather will be verified via channel whenever needed to check given a client request
@@ -38,6 +42,7 @@ impl Mapping {
 
     pub fn start_handler(
         &mut self,
+        config: &Config,
         directory_path: &str,
         handler_mapping: &mut HandlerMapping,
         trace_tx: Arc<
@@ -50,7 +55,12 @@ impl Mapping {
                 message: String::from("Handler already up"),
             }
         } else {
-            match self.spawn_handler_thread(directory_path.to_string(), handler_mapping, trace_tx) {
+            match self.spawn_handler_thread(
+                config,
+                directory_path.to_string(),
+                handler_mapping,
+                trace_tx,
+            ) {
                 Ok(_) => HandlerStateResponse {
                     is_alive: true,
                     message: String::from("Started handler"),
@@ -65,6 +75,7 @@ impl Mapping {
 
     pub fn spawn_handler_thread(
         &mut self,
+        config: &Config,
         directory_path: String,
         handler_mapping: &mut HandlerMapping,
         trace_tx: Arc<
@@ -73,18 +84,27 @@ impl Mapping {
     ) -> Result<(), String> {
         let path = PathBuf::from(directory_path.clone());
         let config_path = PathBuf::from(&handler_mapping.handler_config_path);
+        let state_dir_path = config.state_dir_path();
         match fs::read(&config_path) {
             Ok(data) => {
                 match PipelineConfig::try_from(data) {
                     Ok(config) => {
+                        let handler = PipelineHandler::new(path, state_dir_path, config, trace_tx)
+                            .map_err(|err| format!("Invalid pipeline config.\nPath: {:?}\nError: {}", config_path, err))?;
                         let (events_tx, events_rx) = crossbeam::channel::unbounded();
                         let events_thread_tx = events_tx.clone();
-                        let mut watcher: RecommendedWatcher = Watcher::new_immediate(move |res| events_thread_tx.send(res).unwrap()).unwrap();
-                        let _ = watcher.configure(notify::Config::PreciseEvents(true));
-                        thread::spawn(move || {
-                            let mut handler = PipelineHandler::new(config, trace_tx);
-                            handler.watch(&path, watcher, events_rx);
-                        });
+                        let event_fn = move |res| events_thread_tx.send(res).unwrap();
+                        match handler.config.watcher {
+                            WatcherKind::Native => {
+                                let mut watcher: RecommendedWatcher = Watcher::new_immediate(event_fn).unwrap();
+                                let _ = watcher.configure(notify::Config::PreciseEvents(true));
+                                spawn_watching_thread(handler, watcher, events_rx);
+                            }
+                            WatcherKind::Poll => {
+                                let watcher = PollWatcher::with_delay(Arc::new(Mutex::new(event_fn)), handler.config.poll_interval()).unwrap();
+                                spawn_watching_thread(handler, watcher, events_rx);
+                            }
+                        }
                         // Insert or update the value of the current handled directory
                         handler_mapping.watcher_tx = Option::Some(events_tx);
                         self.directory_mapping.insert(directory_path, handler_mapping.to_owned());
@@ -143,6 +163,18 @@ impl Mapping {
     }
 }
 
+fn spawn_watching_thread<W>(
+    mut handler: PipelineHandler,
+    watcher: W,
+    events_rx: Receiver<Result<notify::Event, notify::Error>>,
+) where
+    W: Watcher + Send + 'static,
+{
+    thread::spawn(move || {
+        handler.watch(watcher, events_rx);
+    });
+}
+
 impl TryFrom<Vec<u8>> for Mapping {
     type Error = &'static str;
 
@@ -25,8 +25,7 @@ impl HandlerService for Server {
         let request_directory_path = request.directory_path.as_str();
         if mapping
             .directory_mapping
-            .get(request_directory_path)
-            .is_some()
+            .contains_key(request_directory_path)
         {
             Ok(Response::new(HandlerStateResponse {
                 is_alive: true,
@@ -71,6 +70,7 @@ impl HandlerService for Server {
                 }
                 let trace_tx = self.handlers_trace_tx.clone();
                 match mapping.spawn_handler_thread(
+                    &self.config,
                     request.directory_path,
                     &mut handler_mapping,
                     trace_tx,
@@ -151,7 +151,8 @@ impl HandlerService for Server {
                     )));
                 }
                 let trace_tx = self.handlers_trace_tx.clone();
-                let response = mapping.start_handler(directory_path, handler_mapping, trace_tx);
+                let response =
+                    mapping.start_handler(&self.config, directory_path, handler_mapping, trace_tx);
                 states_map.insert(directory_path.to_owned(), response);
                 Ok(Response::new(HandlerStatesMapResponse { states_map }))
             }
@@ -168,8 +169,12 @@ impl HandlerService for Server {
                         mapping.clone().directory_mapping.iter_mut()
                     {
                         let trace_tx = self.handlers_trace_tx.clone();
-                        let response =
-                            mapping.start_handler(directory_path, handler_mapping, trace_tx);
+                        let response = mapping.start_handler(
+                            &self.config,
+                            directory_path,
+                            handler_mapping,
+                            trace_tx,
+                        );
                         states_map.insert(directory_path.to_owned(), response);
                     }
                 } else {
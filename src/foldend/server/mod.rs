@@ -41,8 +41,12 @@ impl Server {
     fn convert_trace_channel_reciever_to_stream(&self) -> TraceHandlerStream {
         let mut rx = self.handlers_trace_tx.subscribe();
         Box::pin(async_stream::stream! {
-            while let Ok(item) = rx.recv().await {
-                yield item;
+            loop {
+                match rx.recv().await {
+                    Ok(item) => yield item,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue, // Slow clients miss messages, but keep tracing
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
             }
         })
     }
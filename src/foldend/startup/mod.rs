@@ -22,6 +22,8 @@ use generated_types::{
     StartHandlerRequest,
 };
 
+const TRACE_CHANNEL_CAPACITY: usize = 1024; // Messages buffered for slow trace clients. Command output is streamed line by line
+
 fn construct_app<'a, 'b>() -> App<'a, 'b> {
     App::new("Foldend")
         .version(crate_version!())
@@ -107,7 +109,7 @@ async fn startup_server(
 ) -> Result<(), Box<dyn std::error::Error>> {
     // Setup tracing
     let file_appender = tracing_appender::rolling::daily(
-        &config.tracing_file_path.parent().unwrap(),
+        config.tracing_file_path.parent().unwrap(),
         &config.tracing_file_path,
     );
     let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
@@ -118,7 +120,7 @@ async fn startup_server(
     tracing::subscriber::set_global_default(subscriber).expect("Unable to set a global collector");
 
     let socket = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), config.port);
-    let (trace_tx, _) = broadcast::channel(10);
+    let (trace_tx, _) = broadcast::channel(TRACE_CHANNEL_CAPACITY);
     let server = Server {
         config: Arc::new(config),
         mapping: Arc::new(RwLock::new(mapping)),
@@ -172,7 +174,7 @@ fn get_mapping(config: &Config) -> Mapping {
 }
 
 fn get_config(file_path: &Path) -> Result<Config, Box<dyn std::error::Error>> {
-    match fs::read(&file_path) {
+    match fs::read(file_path) {
         Ok(data) => Ok(Config::try_from(data)?),
         Err(e) => Err(e.into()),
     }